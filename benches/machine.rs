@@ -0,0 +1,184 @@
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use differential_logic::{Machine, PackedMachine, TermTable, classify};
+use std::collections::HashMap;
+use std::hint::black_box;
+
+// machines over 2 to 4 variables with every third term true
+fn machines() -> impl Iterator<Item = Machine> {
+    (2..=4).map(|variables| {
+        let mut index = 0;
+        Machine::new(variables, |_| {
+            index += 1;
+            index % 3 == 0
+        })
+    })
+}
+
+fn observables(c: &mut Criterion) {
+    for machine in machines() {
+        let variables = machine.variables();
+        c.bench_function(&format!("get/{variables}"), |b| {
+            b.iter(|| {
+                (0..variables)
+                    .filter(|&variable| black_box(&machine).get(variable))
+                    .count()
+            })
+        });
+        // before the table rework, `get` looked its index up in a map from terms to indices, with a
+        // freshly allocated key; this replays that lookup for comparison with the direct index
+        let index_of = machine
+            .table()
+            .terms()
+            .iter()
+            .enumerate()
+            .map(|(index, term)| (term.to_vec(), index))
+            .collect::<HashMap<_, _>>();
+        let mut group = c.benchmark_group(format!("get_before_after/{variables}"));
+        group.bench_function("hash_lookup", |b| {
+            b.iter(|| {
+                (0..variables)
+                    .filter(|&variable| black_box(&machine).values()[index_of[&vec![variable]]])
+                    .count()
+            })
+        });
+        group.bench_function("direct", |b| {
+            b.iter(|| {
+                (0..variables)
+                    .filter(|&variable| black_box(&machine).get(variable))
+                    .count()
+            })
+        });
+        group.finish();
+        c.bench_function(&format!("observables/{variables}"), |b| {
+            b.iter(|| black_box(&machine).observables())
+        });
+        // the pattern of simulation loops: flip, then read the state
+        c.bench_function(&format!("flip_observables/{variables}"), |b| {
+            let mut machine = machine.clone();
+            let mut variable = 0;
+            b.iter(|| {
+                machine.flip(variable);
+                variable = (variable + 1) % variables;
+                machine.observables()
+            })
+        });
+    }
+}
+
+fn flip(c: &mut Criterion) {
+    for machine in machines() {
+        let variables = machine.variables();
+        c.bench_function(&format!("flip/{variables}"), |b| {
+            let mut machine = machine.clone();
+            b.iter(|| machine.flip(black_box(variables - 1)))
+        });
+        c.bench_function(&format!("set/{variables}"), |b| {
+            let mut machine = machine.clone();
+            let mut value = false;
+            b.iter(|| {
+                value = !value;
+                machine.set(black_box(0), value)
+            })
+        });
+    }
+    // a flip only visits the terms starting with the flipped variable, a sixth of this table
+    let wide = Machine::new(6, |term| term.len() % 2 == 0);
+    c.bench_function("flip/6", |b| {
+        b.iter_batched_ref(
+            || wide.clone(),
+            |machine| machine.flip(black_box(5)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+// the dense and the bit-packed backend side by side, each group comparing `dense` and `packed`
+fn packed(c: &mut Criterion) {
+    for variables in [3, 6] {
+        let dense = Machine::new(variables, |term| term.len() % 2 == 0);
+        let packed = PackedMachine::from(&dense);
+        let mut group = c.benchmark_group(format!("backend_flip/{variables}"));
+        group.bench_function("dense", |b| {
+            b.iter_batched_ref(
+                || dense.clone(),
+                |machine| machine.flip(black_box(variables - 1)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("packed", |b| {
+            b.iter_batched_ref(
+                || packed.clone(),
+                |machine| machine.flip(black_box(variables - 1)),
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+
+        let mut group = c.benchmark_group(format!("backend_set/{variables}"));
+        group.bench_function("dense", |b| {
+            let mut machine = dense.clone();
+            let mut value = false;
+            b.iter(|| {
+                value = !value;
+                machine.set(black_box(0), value)
+            })
+        });
+        group.bench_function("packed", |b| {
+            let mut machine = packed.clone();
+            let mut value = false;
+            b.iter(|| {
+                value = !value;
+                machine.set(black_box(0), value)
+            })
+        });
+        group.finish();
+    }
+
+    // flips every variable of every machine over 3 variables; the packed side includes converting
+    // each enumerated machine
+    let mut group = c.benchmark_group("backend_enumerate/3");
+    group.bench_function("dense", |b| {
+        b.iter(|| {
+            Machine::iter_all(3)
+                .filter(|machine| {
+                    let mut machine = machine.clone();
+                    (0..3).for_each(|variable| machine.flip(variable));
+                    machine.get(0)
+                })
+                .count()
+        })
+    });
+    group.bench_function("packed", |b| {
+        b.iter(|| {
+            Machine::iter_all(3)
+                .filter(|machine| {
+                    let mut machine = PackedMachine::from(machine);
+                    (0..3).for_each(|variable| machine.flip(variable));
+                    machine.get(0)
+                })
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn new(c: &mut Criterion) {
+    for variables in 2..=4 {
+        TermTable::shared(variables);
+        c.bench_function(&format!("new/{variables}"), |b| {
+            b.iter(|| Machine::new(black_box(variables), |term| term.len() == 2))
+        });
+    }
+}
+
+// there are 2^64 machines over 4 variables, too many to classify
+fn classification(c: &mut Criterion) {
+    for variables in 2..=3 {
+        c.bench_function(&format!("classify/{variables}"), |b| {
+            b.iter(|| classify(black_box(variables)).len())
+        });
+    }
+}
+
+criterion_group!(benches, observables, flip, packed, new, classification);
+criterion_main!(benches);
@@ -0,0 +1,188 @@
+//! Statistics over the flip-equivalence classes of all machines over a few variables.
+
+use crate::Machine;
+use crate::collections::HashMap;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The statistics of every flip-equivalence class over some number of variables, as computed by
+/// `census`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Census {
+    pub variables: usize,
+    /// One entry per class, in the order of `classify`.
+    pub classes: Vec<ClassCensus>,
+}
+
+/// The statistics of one flip-equivalence class, whose members are the states of its flip graph.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClassCensus {
+    /// The member with the smallest signature.
+    pub representative: Machine,
+    /// The number of members.
+    pub states: usize,
+    /// For each variable, the cycles of the permutation that flipping it induces on the members,
+    /// as pairs of a cycle length and the number of cycles of that length, by increasing length.
+    /// As flipping a variable of a `Machine` is an involution, all cycles have length 2.
+    pub cycles: Vec<Vec<(usize, usize)>>,
+    /// The number of members that flipping some variable leaves unchanged.
+    pub fixed_points: usize,
+    /// The greatest number of flips needed to get from one member to another.
+    pub diameter: usize,
+}
+
+/// Computes the statistics of each flip-equivalence class of the machines over `variables`
+/// variables.
+///
+/// Panics if `variables` is more than 3, as `classify` does.
+pub fn census(variables: usize) -> Census {
+    let classes = Machine::flip_equivalence_classes(variables)
+        .into_iter()
+        .map(|members| class_census(variables, members))
+        .collect();
+    Census { variables, classes }
+}
+
+// `members` is a class ordered by signature
+fn class_census(variables: usize, members: Vec<Machine>) -> ClassCensus {
+    let index_of = (members.iter().enumerate())
+        .map(|(index, machine)| (machine.signature().unwrap(), index))
+        .collect::<HashMap<_, _>>();
+    // the member that flipping each variable in each member gives
+    let flips = (0..variables)
+        .map(|variable| {
+            (members.iter())
+                .map(|machine| {
+                    let mut flipped = machine.clone();
+                    flipped.flip(variable);
+                    index_of[&flipped.signature().unwrap()]
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let fixed_points = (0..members.len())
+        .filter(|&index| flips.iter().any(|flip| flip[index] == index))
+        .count();
+    let diameter = (0..members.len())
+        .map(|start| eccentricity(&flips, members.len(), start))
+        .max()
+        .unwrap_or(0);
+    ClassCensus {
+        representative: members[0].clone(),
+        states: members.len(),
+        cycles: flips.iter().map(|flip| cycle_type(flip)).collect(),
+        fixed_points,
+        diameter,
+    }
+}
+
+// the number of cycles of each length of the permutation `flip`
+fn cycle_type(flip: &[usize]) -> Vec<(usize, usize)> {
+    let mut seen = vec![false; flip.len()];
+    let mut counts = BTreeMap::new();
+    for start in 0..flip.len() {
+        let mut length = 0;
+        let mut index = start;
+        while !seen[index] {
+            seen[index] = true;
+            index = flip[index];
+            length += 1;
+        }
+        if length > 0 {
+            *counts.entry(length).or_insert(0) += 1;
+        }
+    }
+    counts.into_iter().collect()
+}
+
+// the greatest number of flips from `start` to another member
+fn eccentricity(flips: &[Vec<usize>], states: usize, start: usize) -> usize {
+    let mut distances = vec![None; states];
+    distances[start] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    let mut farthest = 0;
+    while let Some(index) = queue.pop_front() {
+        let distance = distances[index].unwrap();
+        farthest = distance;
+        for flip in flips {
+            if distances[flip[index]].is_none() {
+                distances[flip[index]] = Some(distance + 1);
+                queue.push_back(flip[index]);
+            }
+        }
+    }
+    farthest
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::census;
+    use crate::{Machine, classify};
+
+    #[test]
+    fn census_two() {
+        let census = census(2);
+        let classes = classify(2);
+        assert_eq!(census.classes.len(), classes.len());
+        for (class, expected) in census.classes.iter().zip(classes.representatives()) {
+            assert_eq!(&class.representative, expected);
+            assert_eq!(
+                class.states,
+                classes.sizes()[classes.class_of(expected).unwrap()]
+            );
+            assert_eq!(
+                class.cycles,
+                [[(2, class.states / 2)], [(2, class.states / 2)]]
+            );
+            assert_eq!(class.fixed_points, 0);
+        }
+        // with no terms of two variables, the class is the four assignments of the variables
+        let independent = &census.classes[0];
+        assert_eq!(independent.representative, Machine::new(2, |_| false));
+        assert_eq!((independent.states, independent.diameter), (4, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "too many machines over 4 variables")]
+    fn four_variables() {
+        census(4);
+    }
+
+    #[test]
+    fn census_three() {
+        let census = census(3);
+        assert_eq!(census.classes.len(), 2024);
+        assert_eq!(
+            census
+                .classes
+                .iter()
+                .map(|class| class.states)
+                .sum::<usize>(),
+            1 << 15
+        );
+        assert!(
+            census
+                .classes
+                .iter()
+                .all(|class| class.diameter < class.states && class.fixed_points == 0)
+        );
+        assert!(
+            (census.classes.iter())
+                .all(|class| class.states.is_power_of_two() && (2..=64).contains(&class.states))
+        );
+        // with every pair true, flipping any variable flips all of them
+        let smallest = census
+            .classes
+            .iter()
+            .min_by_key(|class| class.states)
+            .unwrap();
+        assert_eq!(
+            smallest.representative,
+            Machine::new(3, |term| term.len() == 2)
+        );
+        assert_eq!((smallest.states, smallest.diameter), (2, 1));
+        let widest = census.classes.iter().map(|class| class.diameter).max();
+        assert_eq!(widest, Some(6));
+    }
+}
@@ -0,0 +1,67 @@
+//! `proptest` strategies for machines and flip sequences.
+//!
+//! Machines shrink towards fewer variables first and then towards clearing their terms; flip
+//! sequences shrink towards shorter sequences of smaller variables.
+
+use crate::{Machine, num_terms};
+use proptest::prelude::*;
+
+/// Machines with at most `max_variables` variables and uniformly random term values.
+pub fn machines(max_variables: usize) -> impl Strategy<Value = Machine> {
+    (0..=max_variables).prop_flat_map(|variables| {
+        proptest::collection::vec(any::<bool>(), num_terms(variables))
+            .prop_map(move |values| Machine::from_values(variables, values).unwrap())
+    })
+}
+
+/// Machines as by `machines`, each with a sequence of at most `max_flips` of its variables.
+pub fn machines_with_flips(
+    max_variables: usize,
+    max_flips: usize,
+) -> impl Strategy<Value = (Machine, Vec<usize>)> {
+    machines(max_variables).prop_flat_map(move |machine| {
+        let flips = if machine.variables() == 0 {
+            Just(Vec::new()).boxed()
+        } else {
+            proptest::collection::vec(0..machine.variables(), 0..=max_flips).boxed()
+        };
+        (Just(machine), flips)
+    })
+}
+
+impl Arbitrary for Machine {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Machines with at most 3 variables.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        machines(3).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+    use crate::arbitrary::machines_with_flips;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn flips_are_involutions((machine, flips) in machines_with_flips(3, 8)) {
+            let mut flipped = machine.clone();
+            for &variable in &flips {
+                flipped.flip(variable);
+            }
+            for &variable in flips.iter().rev() {
+                flipped.flip(variable);
+            }
+            prop_assert_eq!(flipped, machine);
+        }
+
+        #[test]
+        fn signature_round_trip(machine in any::<Machine>()) {
+            let signature = machine.signature().unwrap();
+            prop_assert_eq!(Machine::from_signature(machine.variables(), signature), Ok(machine));
+        }
+    }
+}
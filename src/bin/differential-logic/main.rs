@@ -0,0 +1,211 @@
+//! Command-line exploration of machines, built with the `cli` feature.
+//!
+//! Machines are read from a file, or from standard input if the path is `-`, in the text format of
+//! `differential_logic::text` or, if the input starts with `{`, in the JSON format of the `serde`
+//! support. The REPL and scripts use the command language described in `session`.
+
+mod session;
+
+use differential_logic::Machine;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use session::{COMMANDS, Session};
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: differential-logic <command>
+
+commands:
+  show <machine>     print the machine's terms grouped by order
+  dot <machine>      print the graph of machines reachable by flips in DOT format
+  explore <machine>  start a REPL on the machine
+  repl               start a REPL on a machine without variables
+  script <file>      run the commands in a file, stopping at the first failing one
+  classify <n>       print the flip equivalence classes of the machines over n <= 3 variables";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [command, path] if command == "show" => println!("{}", load(path)?.pretty()),
+        [command, path] if command == "dot" => print!("{}", load(path)?.to_fsm().to_dot()),
+        [command, path] if command == "explore" => repl(Session::new(load(path)?))?,
+        [command] if command == "repl" => repl(Session::new(Machine::new(0, |_| false)))?,
+        [command, path] if command == "script" => script(&read(path)?)?,
+        [command, variables] if command == "classify" => {
+            let variables = variables
+                .parse()
+                .map_err(|_| format!("{variables:?} is not a variable count"))?;
+            print!("{}", classify(variables)?);
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+fn read(path: &str) -> Result<String, String> {
+    let mut text = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut text)
+    } else {
+        std::fs::File::open(path).and_then(|mut file| file.read_to_string(&mut text))
+    }
+    .map_err(|error| format!("cannot read {path}: {error}"))?;
+    Ok(text)
+}
+
+fn load(path: &str) -> Result<Machine, String> {
+    session::parse(&read(path)?)
+}
+
+fn repl(mut session: Session) -> Result<(), String> {
+    let mut editor =
+        Editor::<Completion, DefaultHistory>::new().map_err(|error| error.to_string())?;
+    loop {
+        editor.set_helper(Some(Completion {
+            variables: session.variable_names(),
+        }));
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(()),
+            Err(error) => return Err(error.to_string()),
+        };
+        if !line.trim().is_empty() {
+            editor
+                .add_history_entry(line.as_str())
+                .map_err(|error| error.to_string())?;
+        }
+        if line.trim() == "quit" {
+            return Ok(());
+        }
+        match session.execute(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{output}"),
+            Err(message) => println!("error: {message}"),
+        }
+    }
+}
+
+fn script(text: &str) -> Result<(), String> {
+    let mut session = Session::new(Machine::new(0, |_| false));
+    for (index, line) in text.lines().enumerate() {
+        if line.trim() == "quit" {
+            break;
+        }
+        match session.execute(line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{output}"),
+            Err(message) => return Err(format!("line {}: {message}", index + 1)),
+        }
+    }
+    Ok(())
+}
+
+// completes command names at the start of a line and variable names elsewhere
+struct Completion {
+    variables: Vec<String>,
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = pos
+            - line[..pos]
+                .chars()
+                .rev()
+                .take_while(|&c| !c.is_whitespace() && c != '=')
+                .map(char::len_utf8)
+                .sum::<usize>();
+        let word = &line[start..pos];
+        let candidates = if line[..start].trim().is_empty() {
+            COMMANDS
+                .iter()
+                .map(|command| command.to_string())
+                .filter(|command| command.starts_with(word))
+                .collect()
+        } else {
+            self.variables
+                .iter()
+                .filter(|variable| variable.starts_with(word))
+                .cloned()
+                .collect()
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+fn classify(variables: usize) -> Result<String, String> {
+    if variables > 3 {
+        return Err(format!(
+            "there are too many machines over {variables} variables to classify"
+        ));
+    }
+    let classes = Machine::flip_equivalence_classes(variables);
+    let mut text = format!("{} classes\n", classes.len());
+    for (index, class) in classes.iter().enumerate() {
+        text += &format!(
+            "class {index}: {} machines, e.g. {}\n",
+            class.len(),
+            class[0]
+        );
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{classify, script};
+    use differential_logic::Machine;
+
+    #[test]
+    fn classes() {
+        assert!(classify(2).unwrap().starts_with(&format!(
+            "{} classes\n",
+            Machine::flip_equivalence_classes(2).len()
+        )));
+        assert!(classify(4).is_err());
+    }
+
+    #[test]
+    fn failing_script() {
+        assert_eq!(
+            script("machine 01:+\nflip 0\nassert 1=1\nquit\nflip x"),
+            Ok(())
+        );
+        assert_eq!(
+            script("machine 01:+\n\nassert 1=1"),
+            Err("line 3: assertion failed: 1 is 0".to_string())
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! The command language of the REPL and of scripts: one command per line, with `#` starting a
+//! comment. Variables are given by name, once they have been named, or by index.
+
+use differential_logic::{Machine, Snapshot};
+use std::collections::HashMap;
+
+pub const COMMANDS: &[&str] = &[
+    "machine", "names", "flip", "set", "get", "state", "assert", "snapshot", "restore", "solve",
+    "show", "dot", "help", "quit",
+];
+
+pub const HELP: &str = "\
+commands:
+  machine <text>                 replace the machine by one in the text format, e.g. `01:+, 10:-`
+  names <name>...                name the variables, in order
+  flip <variable>                flip a variable and print the state
+  set <variable> <0 or 1>        set a variable and print the state
+  get <variable>                 print the value of a variable
+  state                          print the values of all variables
+  assert <variable>=<0 or 1>...  fail unless the variables have the given values
+  snapshot <name>                remember the current values under a name
+  restore <name>                 put back the values remembered under a name
+  solve <variable>=<0 or 1>...   apply and print a shortest sequence of flips giving the
+                                 variables the given values
+  show                           print the machine's terms grouped by order
+  dot                            print the graph of machines reachable by flips in DOT format
+  help                           print this help
+  quit                           stop";
+
+/// Reads a machine in the text format or, if `text` starts with `{`, in the JSON format.
+pub fn parse(text: &str) -> Result<Machine, String> {
+    if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).map_err(|error| error.to_string())
+    } else {
+        text.trim().parse().map_err(|error| format!("{error}"))
+    }
+}
+
+pub struct Session {
+    machine: Machine,
+    // empty while the variables have no names
+    names: Vec<String>,
+    snapshots: HashMap<String, Snapshot>,
+}
+
+impl Session {
+    pub fn new(machine: Machine) -> Self {
+        Self {
+            machine,
+            names: Vec::new(),
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// The names of the variables or, while they have none, their indices.
+    pub fn variable_names(&self) -> Vec<String> {
+        if self.names.is_empty() {
+            (0..self.machine.variables())
+                .map(|variable| variable.to_string())
+                .collect()
+        } else {
+            self.names.clone()
+        }
+    }
+
+    /// Applies one command other than `quit` and returns what to print.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let line = line.split('#').next().unwrap().trim();
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match words[..] {
+            [] => Ok(String::new()),
+            ["machine", ..] => {
+                self.machine = parse(line["machine".len()..].trim())?;
+                self.names.clear();
+                Ok(self.state())
+            }
+            ["names", ref names @ ..] => {
+                let named = self
+                    .machine
+                    .clone()
+                    .with_names(names)
+                    .map_err(|error| error.to_string())?;
+                self.names = named.names().to_vec();
+                Ok(String::new())
+            }
+            ["flip", variable] => {
+                let variable = self.variable(variable)?;
+                self.machine.flip(variable);
+                Ok(self.state())
+            }
+            ["set", variable, value] => {
+                let variable = self.variable(variable)?;
+                self.machine.set(variable, parse_value(value)?);
+                Ok(self.state())
+            }
+            ["get", variable] => {
+                let variable = self.variable(variable)?;
+                Ok(u8::from(self.machine.get(variable)).to_string())
+            }
+            ["state"] => Ok(self.state()),
+            ["assert", ref assignments @ ..] => {
+                for (variable, value) in self.assignments(assignments)? {
+                    if self.machine.get(variable) != value {
+                        return Err(format!(
+                            "assertion failed: {} is {}",
+                            self.variable_names()[variable],
+                            u8::from(!value)
+                        ));
+                    }
+                }
+                Ok(String::new())
+            }
+            ["snapshot", name] => {
+                self.snapshots
+                    .insert(name.to_string(), self.machine.snapshot());
+                Ok(String::new())
+            }
+            ["restore", name] => {
+                let snapshot = self
+                    .snapshots
+                    .get(name)
+                    .ok_or_else(|| format!("there is no snapshot named {name:?}"))?;
+                self.machine
+                    .restore(snapshot)
+                    .map_err(|error| error.to_string())?;
+                Ok(self.state())
+            }
+            ["solve", ref assignments @ ..] => {
+                let mut target = vec![None; self.machine.variables()];
+                for (variable, value) in self.assignments(assignments)? {
+                    target[variable] = Some(value);
+                }
+                let flips = self
+                    .machine
+                    .solve(&target)
+                    .ok_or("no reachable machine has these values")?;
+                let names = self.variable_names();
+                let mut path = String::from("flips:");
+                for variable in flips {
+                    self.machine.flip(variable);
+                    path += " ";
+                    path += &names[variable];
+                }
+                Ok(format!("{path}\n{}", self.state()))
+            }
+            ["show"] if self.names.is_empty() => Ok(self.machine.pretty().to_string()),
+            ["show"] => Ok(self.machine.pretty().names(&self.names).to_string()),
+            ["dot"] => Ok(self.machine.to_fsm().to_dot().trim_end().to_string()),
+            ["help"] => Ok(HELP.to_string()),
+            _ => Err(format!("unknown command {line:?}; try help")),
+        }
+    }
+
+    // the values of the variables, as digits with variable 0 first or, once they are named, as
+    // `name=value` pairs
+    fn state(&self) -> String {
+        let values =
+            (0..self.machine.variables()).map(|variable| u8::from(self.machine.get(variable)));
+        if self.names.is_empty() {
+            values.map(|value| value.to_string()).collect()
+        } else {
+            self.names
+                .iter()
+                .zip(values)
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    }
+
+    fn variable(&self, word: &str) -> Result<usize, String> {
+        self.names
+            .iter()
+            .position(|name| name == word)
+            .or_else(|| word.parse().ok())
+            .filter(|&variable| variable < self.machine.variables())
+            .ok_or_else(|| format!("{word:?} is not a variable"))
+    }
+
+    fn assignments(&self, words: &[&str]) -> Result<Vec<(usize, bool)>, String> {
+        words
+            .iter()
+            .map(|word| {
+                let (variable, value) = word
+                    .split_once('=')
+                    .ok_or_else(|| format!("{word:?} is not of the form `variable=value`"))?;
+                Ok((self.variable(variable)?, parse_value(value)?))
+            })
+            .collect()
+    }
+}
+
+fn parse_value(word: &str) -> Result<bool, String> {
+    match word {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(format!("{word:?} is neither 0 nor 1")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::session::{Session, parse};
+    use differential_logic::Machine;
+
+    #[test]
+    fn formats() {
+        let machine = Machine::new(2, |term| term == [0, 1]);
+        assert_eq!(parse("01:+\n"), Ok(machine.clone()));
+        assert_eq!(
+            parse(&serde_json::to_string(&machine).unwrap()),
+            Ok(machine)
+        );
+        assert!(parse("01:x").is_err());
+    }
+
+    #[test]
+    fn commands() {
+        let mut session = Session::new(Machine::new(2, |term| term == [0, 1]));
+        assert_eq!(session.execute("flip 0"), Ok("11".to_string()));
+        assert_eq!(session.execute(" set 1 0 # comment"), Ok("10".to_string()));
+        assert_eq!(session.execute("get 0"), Ok("1".to_string()));
+        assert_eq!(session.execute("state"), Ok("10".to_string()));
+        assert_eq!(session.execute(""), Ok(String::new()));
+        assert!(session.execute("dot").unwrap().starts_with("digraph"));
+        assert!(session.execute("flip 2").is_err());
+        assert!(session.execute("set 0 2").is_err());
+        assert!(session.execute("jump").is_err());
+    }
+
+    #[test]
+    fn script() {
+        let mut session = Session::new(Machine::new(0, |_| false));
+        let script = "
+            machine 02:+, 12:+
+            names clock reset q
+            snapshot start
+            flip clock
+            assert clock=1 q=1
+            restore start
+            solve reset=1 q=0
+        ";
+        let output = script
+            .lines()
+            .map(|line| session.execute(line))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(output[4], "clock=1 reset=0 q=1");
+        assert_eq!(output[7], "flips: clock reset\nclock=1 reset=1 q=0");
+        assert_eq!(session.variable_names(), ["clock", "reset", "q"]);
+        assert_eq!(
+            session.execute("assert q=1"),
+            Err("assertion failed: q is 0".to_string())
+        );
+        assert!(session.execute("restore end").is_err());
+        assert!(session.execute("names a a b").is_err());
+        assert!(session.execute("solve q").is_err());
+    }
+}
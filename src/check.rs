@@ -0,0 +1,196 @@
+//! Model checking of temporal properties over the graph of machines reachable by flips, in which
+//! every flip is a possible next step.
+
+use crate::collections::HashMap;
+use crate::{GenericMachine, Value};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+type Predicate<'a, T> = Box<dyn Fn(&GenericMachine<T>) -> bool + 'a>;
+
+pub enum Property<'a, T: Value> {
+    /// The predicate holds in every reachable machine.
+    Always(Predicate<'a, T>),
+    /// The predicate holds in no reachable machine.
+    Never(Predicate<'a, T>),
+    /// Every infinite sequence of flips eventually reaches a machine in which the predicate holds.
+    /// A machine without variables can never be flipped, so it stays as it is forever.
+    Eventually(Predicate<'a, T>),
+}
+
+impl<'a, T: Value> Property<'a, T> {
+    pub fn always(predicate: impl Fn(&GenericMachine<T>) -> bool + 'a) -> Self {
+        Property::Always(Box::new(predicate))
+    }
+
+    pub fn never(predicate: impl Fn(&GenericMachine<T>) -> bool + 'a) -> Self {
+        Property::Never(Box::new(predicate))
+    }
+
+    pub fn eventually(predicate: impl Fn(&GenericMachine<T>) -> bool + 'a) -> Self {
+        Property::Eventually(Box::new(predicate))
+    }
+}
+
+/// A run violating a property: the flips in `prefix`, followed by the flips in `cycle` repeated
+/// forever. `cycle` is empty for properties that are violated by a single machine, which is then
+/// the one reached by `prefix`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Counterexample {
+    pub prefix: Vec<usize>,
+    pub cycle: Vec<usize>,
+}
+
+/// Explores the machines reachable from `start` and returns `Ok` if `property` holds, or a shortest
+/// counterexample for `Always` and `Never`, and some counterexample for `Eventually`.
+pub fn check<T: Value>(
+    start: &GenericMachine<T>,
+    property: &Property<T>,
+) -> Result<(), Counterexample> {
+    let violation = |predicate: &dyn Fn(&GenericMachine<T>) -> bool| {
+        start
+            .reachable_states()
+            .find(|(machine, _)| predicate(machine))
+            .map(|(_, prefix)| Counterexample {
+                prefix,
+                cycle: Vec::new(),
+            })
+    };
+    let counterexample = match property {
+        Property::Always(predicate) => violation(&|machine| !predicate(machine)),
+        Property::Never(predicate) => violation(predicate),
+        Property::Eventually(predicate) => avoiding_cycle(start, predicate),
+    };
+    counterexample.map_or(Ok(()), Err)
+}
+
+// searches depth-first, among the machines reachable from `start` without passing one satisfying
+// `predicate`, for a cycle, which can then be followed forever
+fn avoiding_cycle<T: Value>(
+    start: &GenericMachine<T>,
+    predicate: &dyn Fn(&GenericMachine<T>) -> bool,
+) -> Option<Counterexample> {
+    if predicate(start) {
+        return None;
+    }
+    if start.variables() == 0 {
+        return Some(Counterexample {
+            prefix: Vec::new(),
+            cycle: Vec::new(),
+        });
+    }
+    // for each visited machine, whether it is still on the stack
+    let mut on_stack = HashMap::from([(start.clone(), true)]);
+    // the machines on the current path, each with the next variable to flip
+    let mut stack = vec![(start.clone(), 0)];
+    // the flips between consecutive machines on the stack
+    let mut flips = Vec::new();
+    while let Some((machine, next)) = stack.last_mut() {
+        let variable = *next;
+        if variable == machine.variables() {
+            on_stack.insert(machine.clone(), false);
+            stack.pop();
+            flips.pop();
+            continue;
+        }
+        *next += 1;
+        let mut neighbour = machine.clone();
+        neighbour.flip(variable);
+        if predicate(&neighbour) {
+            continue;
+        }
+        match on_stack.get(&neighbour) {
+            Some(true) => {
+                let position = stack
+                    .iter()
+                    .position(|(machine, _)| *machine == neighbour)
+                    .unwrap();
+                let mut cycle = flips.split_off(position);
+                cycle.push(variable);
+                return Some(Counterexample {
+                    prefix: flips,
+                    cycle,
+                });
+            }
+            Some(false) => {}
+            None => {
+                on_stack.insert(neighbour.clone(), true);
+                stack.push((neighbour, 0));
+                flips.push(variable);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::{Counterexample, Property, check};
+    use crate::{GenericMachine, Machine};
+
+    #[test]
+    fn safety() {
+        // flipping either variable flips both, so they never differ
+        let locked = Machine::new(2, |term| term.len() == 2);
+        assert_eq!(
+            locked.check(&Property::always(|machine| machine.get(0) == machine.get(1))),
+            Ok(())
+        );
+        assert_eq!(
+            locked.check(&Property::never(|machine| machine.get(1))),
+            Err(Counterexample {
+                prefix: vec![0],
+                cycle: vec![]
+            })
+        );
+
+        let chain = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        let counterexample = chain
+            .check(&Property::always(|machine| machine.observables() != 0b101))
+            .unwrap_err();
+        let mut machine = chain.clone();
+        for &variable in &counterexample.prefix {
+            machine.flip(variable);
+        }
+        assert_eq!(machine.observables(), 0b101);
+    }
+
+    #[test]
+    fn liveness() {
+        // follows a counterexample and checks that it never satisfies `predicate` and that its
+        // cycle returns where it started
+        fn verify(system: &Machine, predicate: impl Fn(&Machine) -> bool, run: &Counterexample) {
+            let mut machine = system.clone();
+            for &variable in &run.prefix {
+                assert!(!predicate(&machine));
+                machine.flip(variable);
+            }
+            let entry = machine.clone();
+            for &variable in &run.cycle {
+                assert!(!predicate(&machine));
+                machine.flip(variable);
+            }
+            assert_eq!(machine, entry);
+        }
+
+        let system = Machine::new(2, |_| false);
+        let reached = |target: u64| move |machine: &Machine| machine.observables() == target;
+        assert_eq!(check(&system, &Property::eventually(reached(0b00))), Ok(()));
+        assert_eq!(
+            check(
+                &system,
+                &Property::eventually(|machine| machine.get(0) || machine.get(1))
+            ),
+            Ok(())
+        );
+        for target in [0b01, 0b11] {
+            let run = check(&system, &Property::eventually(reached(target))).unwrap_err();
+            assert!(!run.cycle.is_empty());
+            verify(&system, reached(target), &run);
+        }
+
+        let empty = GenericMachine::<bool>::new(0, |_| false);
+        assert!(check(&empty, &Property::eventually(|_| false)).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{Machine, MachineError};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// The partition of all machines over some number of variables into the connected components of
+/// the relation "one machine becomes the other by a single `flip`".
+#[derive(Clone, Debug)]
+pub struct EquivalenceClasses {
+    variables: usize,
+    // the class of each machine, indexed by signature
+    classes: Vec<usize>,
+    representatives: Vec<Machine>,
+    sizes: Vec<usize>,
+}
+
+/// Computes the flip-equivalence classes of all machines over `variables` variables.
+///
+/// Panics if `variables` is more than 3, as the 2^64 machines over 4 variables cannot be indexed.
+///
+/// With the `rayon` feature, the machines are enumerated and connected in parallel chunks, whose
+/// partial results are merged at the end.
+///
+/// Unlike reachability and the state graphs, classification is only for `Machine`: it indexes
+/// every machine by its `signature`, which other representations and value types lack.
+pub fn classify(variables: usize) -> EquivalenceClasses {
+    assert!(
+        variables <= 3,
+        "too many machines over {variables} variables to classify"
+    );
+    let mut union_find = connect_all(variables);
+    let mut class_of_root = vec![None; union_find.parents.len()];
+    let mut classes = Vec::with_capacity(union_find.parents.len());
+    let mut representatives = Vec::new();
+    let mut sizes = Vec::new();
+    for index in 0..union_find.parents.len() {
+        let root = union_find.find(index);
+        let class = *class_of_root[root].get_or_insert_with(|| {
+            representatives.push(Machine::from_signature(variables, index as u128).unwrap());
+            sizes.push(0);
+            representatives.len() - 1
+        });
+        sizes[class] += 1;
+        classes.push(class);
+    }
+    EquivalenceClasses {
+        variables,
+        classes,
+        representatives,
+        sizes,
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+fn connect_all(variables: usize) -> UnionFind {
+    let len = Machine::iter_all(variables).size_hint().0;
+    let mut union_find = UnionFind::new(len);
+    connect(&mut union_find, variables, 0..len);
+    union_find
+}
+
+#[cfg(feature = "rayon")]
+fn connect_all(variables: usize) -> UnionFind {
+    let len = Machine::iter_all(variables).size_hint().0;
+    let chunk_len = len.div_ceil(rayon::current_num_threads()).max(1);
+    (0..len)
+        .step_by(chunk_len)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|start| {
+            let mut union_find = UnionFind::new(len);
+            connect(
+                &mut union_find,
+                variables,
+                start..len.min(start + chunk_len),
+            );
+            union_find
+        })
+        .reduce(
+            || UnionFind::new(len),
+            |mut union_find, mut other| {
+                union_find.merge(&mut other);
+                union_find
+            },
+        )
+}
+
+// unites each machine whose signature is in `signatures` with its neighbours
+fn connect(union_find: &mut UnionFind, variables: usize, signatures: Range<usize>) {
+    let machines = Machine::iter_all(variables)
+        .skip(signatures.start)
+        .take(signatures.len());
+    for (index, machine) in signatures.zip(machines) {
+        for variable in 0..variables {
+            let mut neighbour = machine.clone();
+            neighbour.flip(variable);
+            union_find.union(index, neighbour.signature().unwrap() as usize);
+        }
+    }
+}
+
+impl EquivalenceClasses {
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// The number of classes.
+    pub fn len(&self) -> usize {
+        self.representatives.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.representatives.is_empty()
+    }
+
+    /// The machine with the smallest signature of each class, ordered by signature.
+    pub fn representatives(&self) -> &[Machine] {
+        &self.representatives
+    }
+
+    /// The number of machines in each class, in the order of `representatives`.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// The index of the class `machine` belongs to. Bounded machines belong to no class.
+    pub fn class_of(&self, machine: &Machine) -> Result<usize, MachineError> {
+        if machine.variables() != self.variables {
+            return Err(MachineError::VariablesMismatch {
+                left: self.variables,
+                right: machine.variables(),
+            });
+        }
+        if machine.table().is_bounded() {
+            return Err(MachineError::MaxOrderMismatch {
+                left: self.variables,
+                right: machine.table().max_order(),
+            });
+        }
+        Ok(self.classes[machine.signature().unwrap() as usize])
+    }
+
+    pub fn representative(&self, machine: &Machine) -> Result<&Machine, MachineError> {
+        Ok(&self.representatives[self.class_of(machine)?])
+    }
+
+    /// Iterates over the machines in the given class, ordered by signature.
+    pub fn members(&self, class: usize) -> impl Iterator<Item = Machine> + '_ {
+        self.classes
+            .iter()
+            .enumerate()
+            .filter(move |&(_, &other)| other == class)
+            .map(|(signature, _)| {
+                Machine::from_signature(self.variables, signature as u128).unwrap()
+            })
+    }
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+        }
+    }
+
+    fn find(&mut self, mut element: usize) -> usize {
+        while self.parents[element] != element {
+            let grandparent = self.parents[self.parents[element]];
+            self.parents[element] = grandparent;
+            element = grandparent;
+        }
+        element
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        match self.ranks[a].cmp(&self.ranks[b]) {
+            Ordering::Less => self.parents[a] = b,
+            Ordering::Greater => self.parents[b] = a,
+            Ordering::Equal => {
+                self.parents[b] = a;
+                self.ranks[a] += 1;
+            }
+        }
+    }
+
+    // adds all unions of `other`, a union-find over the same elements
+    #[cfg(feature = "rayon")]
+    fn merge(&mut self, other: &mut Self) {
+        for element in 0..self.parents.len() {
+            let root = other.find(element);
+            self.union(element, root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Machine, MachineError, classify};
+
+    #[test]
+    fn classify_three() {
+        let classes = classify(3);
+        assert_eq!(classes.len(), 2024);
+        assert_eq!(classes.sizes().iter().sum::<usize>(), 1 << 15);
+        for machine in Machine::all(3) {
+            let class = classes.class_of(&machine).unwrap();
+            for variable in 0..3 {
+                let mut neighbour = machine.clone();
+                neighbour.flip(variable);
+                assert_eq!(classes.class_of(&neighbour), Ok(class));
+            }
+            assert!(classes.representative(&machine).unwrap() <= &machine);
+        }
+        assert_eq!(
+            classes.class_of(&Machine::new(2, |_| false)),
+            Err(MachineError::VariablesMismatch { left: 3, right: 2 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "too many machines over 4 variables to classify")]
+    fn four_variables() {
+        classify(4);
+    }
+
+    #[test]
+    fn members() {
+        let classes = classify(2);
+        for class in 0..classes.len() {
+            let members = classes.members(class).collect::<Vec<_>>();
+            assert_eq!(members.len(), classes.sizes()[class]);
+            assert_eq!(members[0], classes.representatives()[class]);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Exact counts of machines and of classes of machines, computed without enumerating the machines
+//! where possible.
+
+use crate::{Machine, TermTable, classify, relabeling_sources};
+use alloc::vec;
+use itertools::Itertools;
+use num_bigint::BigUint;
+
+/// The number of terms over `variables` variables, `Σ P(variables, k)` for `k` from 1 to
+/// `variables`.
+pub fn terms(variables: usize) -> BigUint {
+    let mut count = BigUint::ZERO;
+    let mut permutations = BigUint::from(1u8);
+    for k in 0..variables {
+        permutations *= variables - k;
+        count += &permutations;
+    }
+    count
+}
+
+/// The number of machines over `variables` variables, `2^terms(variables)`.
+///
+/// Panics if the number of terms does not fit in a `u64`; the count would not fit in memory
+/// anyway.
+pub fn machines(variables: usize) -> BigUint {
+    let terms = u64::try_from(terms(variables)).expect("too many terms");
+    BigUint::from(1u8) << terms
+}
+
+/// The number of classes of machines over `variables` variables that are equal up to relabeling
+/// of variables, that is, the length of `Machine::all_canonical(variables)`.
+///
+/// Counted with Burnside's lemma, as the average over all relabelings of the number of machines
+/// they leave unchanged. This walks every relabeling and the full term table, which is feasible up
+/// to about 7 variables.
+pub fn canonical_machines(variables: usize) -> BigUint {
+    let table = TermTable::shared(variables);
+    let fixed = (0..variables)
+        .permutations(variables)
+        .map(|relabeling| {
+            // a machine is unchanged iff it is constant on each cycle of the relabeled terms
+            let sources = relabeling_sources(&table, &relabeling);
+            let mut visited = vec![false; sources.len()];
+            let mut cycles = 0u64;
+            for start in 0..sources.len() {
+                if visited[start] {
+                    continue;
+                }
+                cycles += 1;
+                let mut index = start;
+                while !visited[index] {
+                    visited[index] = true;
+                    index = sources[index];
+                }
+            }
+            BigUint::from(1u8) << cycles
+        })
+        .sum::<BigUint>();
+    let relabelings = (1..=variables).product::<BigUint>();
+    fixed / relabelings
+}
+
+/// The number of flip-equivalence classes over `variables` variables, as computed by `classify`,
+/// or `None` if there are more than 2^32 machines to search.
+pub fn flip_classes(variables: usize) -> Option<usize> {
+    if terms(variables) > BigUint::from(32u8) {
+        return None;
+    }
+    Some(classify(variables).len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+    use crate::counting::{canonical_machines, flip_classes, machines, terms};
+    use num_bigint::BigUint;
+
+    #[test]
+    fn small() {
+        for variables in 0..=3 {
+            let all = Machine::all(variables);
+            assert_eq!(machines(variables), BigUint::from(all.len()));
+            assert_eq!(
+                canonical_machines(variables),
+                BigUint::from(Machine::all_canonical(variables).len())
+            );
+            assert_eq!(
+                flip_classes(variables),
+                Some(Machine::flip_equivalence_classes(variables).len())
+            );
+        }
+        assert_eq!(terms(3), BigUint::from(15u8));
+        assert_eq!(machines(4), BigUint::from(1u128 << 64));
+        assert_eq!(flip_classes(4), None);
+    }
+
+    #[test]
+    fn large() {
+        assert_eq!(terms(20).to_string(), "6613313319248080000");
+        assert!(terms(25) > BigUint::from(u64::MAX));
+        assert_eq!(terms(5), BigUint::from(325u16));
+        assert_eq!(machines(5).bits(), 326);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::Value;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use itertools::Itertools;
+
+/// The terms whose values differ between two machines over the same terms, in term order, as
+/// returned by `GenericMachine::diff`.
+///
+/// For `bool` machines, the `Display` output lists one term per line, prefixed with `+` if it is
+/// true only in the second machine and `-` if it is true only in the first, with the terms written
+/// as in the text format but without the `.` after single variables, e.g. `+0\n-01`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MachineDiff<'a, T: Value> {
+    pub(crate) variables: usize,
+    pub(crate) changes: Vec<TermChange<'a, T>>,
+}
+
+/// A term whose value differs between two machines.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TermChange<'a, T: Value> {
+    pub term: &'a [usize],
+    /// The value in the machine `diff` was called on.
+    pub old: T,
+    /// The value in the machine passed to `diff`.
+    pub new: T,
+}
+
+impl<'a, T: Value> MachineDiff<'a, T> {
+    pub fn changes(&self) -> &[TermChange<'a, T>] {
+        &self.changes
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The variables whose values differ, in ascending order.
+    pub fn variables(&self) -> impl Iterator<Item = usize> + '_ {
+        self.changes
+            .iter()
+            .filter(|change| change.term.len() == 1)
+            .map(|change| change.term[0])
+    }
+
+    /// The changed terms of the given number of variables.
+    pub fn of_order(&self, order: usize) -> impl Iterator<Item = &TermChange<'a, T>> {
+        self.changes
+            .iter()
+            .filter(move |change| change.term.len() == order)
+    }
+}
+
+impl<'a, T: Value> IntoIterator for MachineDiff<'a, T> {
+    type Item = TermChange<'a, T>;
+    type IntoIter = alloc::vec::IntoIter<TermChange<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+impl Display for MachineDiff<'_, bool> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let separator = if self.variables > 10 { "." } else { "" };
+        let lines = self.changes.iter().map(|change| {
+            let sign = if change.new { '+' } else { '-' };
+            format!("{sign}{}", change.term.iter().join(separator))
+        });
+        write!(f, "{}", lines.format("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::TermChange;
+    use crate::{Machine, Term};
+    use itertools::Itertools;
+
+    #[test]
+    fn listing() {
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2] | [2, 1, 0]));
+        let mut other = system.clone();
+        other.flip(1);
+        other.set_term(&Term::new([2, 1, 0]), false);
+        let diff = system.diff(&other).unwrap();
+        assert_eq!(diff.to_string(), "+1\n+2\n-210");
+        assert_eq!(diff.variables().collect_vec(), [1, 2]);
+        assert_eq!(
+            diff.of_order(3).collect_vec(),
+            [&TermChange {
+                term: &[2, 1, 0][..],
+                old: true,
+                new: false
+            }]
+        );
+        assert!(system.diff(&system).unwrap().to_string().is_empty());
+
+        let wide = Machine::new_bounded(11, 1, |_| false);
+        let mut flipped = wide.clone();
+        flipped.flip(10);
+        assert_eq!(wide.diff(&flipped).unwrap().to_string(), "+10");
+    }
+}
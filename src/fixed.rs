@@ -0,0 +1,300 @@
+use crate::{DifferentialSystem, Machine, MachineError, Term};
+use core::fmt::{Debug, Formatter};
+
+const MAX_VARIABLES: usize = 4;
+const MAX_TERMS: usize = 64;
+// the number of terms of length at least 2 starting with a given variable, for 4 variables
+const MAX_CASCADE: usize = 15;
+
+// the terms and cascades for one variable count, computed at compile time
+struct Layout {
+    len: usize,
+    terms: [([usize; MAX_VARIABLES], usize); MAX_TERMS],
+    // for each variable, the indices of each longer term starting with it and of that term's tail
+    cascades: [[(usize, usize); MAX_CASCADE]; MAX_VARIABLES],
+    cascade_lens: [usize; MAX_VARIABLES],
+}
+
+static LAYOUTS: [Layout; MAX_VARIABLES + 1] =
+    [layout(0), layout(1), layout(2), layout(3), layout(4)];
+
+const fn layout(variables: usize) -> Layout {
+    let mut layout = Layout {
+        len: 0,
+        terms: [([0; MAX_VARIABLES], 0); MAX_TERMS],
+        cascades: [[(0, 0); MAX_CASCADE]; MAX_VARIABLES],
+        cascade_lens: [0; MAX_VARIABLES],
+    };
+    // counting through all tuples of each length in lexicographic order and keeping those without
+    // repeated variables yields the terms in table order
+    let mut len = 1;
+    while len <= variables {
+        let mut tuple = [0; MAX_VARIABLES];
+        loop {
+            if all_distinct(&tuple, len) {
+                layout.terms[layout.len] = (tuple, len);
+                layout.len += 1;
+            }
+            let mut position = len;
+            while position > 0 && tuple[position - 1] == variables - 1 {
+                tuple[position - 1] = 0;
+                position -= 1;
+            }
+            if position == 0 {
+                break;
+            }
+            tuple[position - 1] += 1;
+        }
+        len += 1;
+    }
+    let mut index = 0;
+    while index < layout.len {
+        let (term, len) = layout.terms[index];
+        if len > 1 {
+            let variable = term[0];
+            let mut tail = [0; MAX_VARIABLES];
+            let mut position = 1;
+            while position < len {
+                tail[position - 1] = term[position];
+                position += 1;
+            }
+            let slot = layout.cascade_lens[variable];
+            layout.cascades[variable][slot] = (index, find(&layout, tail, len - 1));
+            layout.cascade_lens[variable] += 1;
+        }
+        index += 1;
+    }
+    layout
+}
+
+const fn all_distinct(tuple: &[usize; MAX_VARIABLES], len: usize) -> bool {
+    let mut i = 0;
+    while i < len {
+        let mut j = i + 1;
+        while j < len {
+            if tuple[i] == tuple[j] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn find(layout: &Layout, term: [usize; MAX_VARIABLES], len: usize) -> usize {
+    let mut index = 0;
+    loop {
+        let (candidate, candidate_len) = layout.terms[index];
+        if candidate_len == len {
+            let mut position = 0;
+            while position < len && candidate[position] == term[position] {
+                position += 1;
+            }
+            if position == len {
+                return index;
+            }
+        }
+        index += 1;
+    }
+}
+
+/// A `bool` machine over a fixed number `N <= 4` of variables, whose term values are packed into a
+/// single `u64` with term layout and cascades computed at compile time. It behaves exactly like the
+/// corresponding `Machine`, without allocating or sharing a term table.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct FixedMachine<const N: usize> {
+    // the term values as a signature, see `Machine::from_signature`
+    signature: u64,
+}
+
+impl<const N: usize> FixedMachine<N> {
+    const LAYOUT: &'static Layout = {
+        assert!(
+            N <= MAX_VARIABLES,
+            "fixed machines have at most 4 variables"
+        );
+        &LAYOUTS[N]
+    };
+
+    pub fn new(mut initial_values_producer: impl FnMut(&[usize]) -> bool) -> Self {
+        let mut machine = Self { signature: 0 };
+        for index in 0..Self::LAYOUT.len {
+            let (term, len) = &Self::LAYOUT.terms[index];
+            if initial_values_producer(&term[..*len]) {
+                machine.toggle(index);
+            }
+        }
+        machine
+    }
+
+    /// The machine whose term values, read as in `Machine::from_signature`, are `signature`.
+    pub fn from_signature(signature: u64) -> Result<Self, MachineError> {
+        if Self::LAYOUT.len < 64 && signature >> Self::LAYOUT.len != 0 {
+            return Err(MachineError::SignatureOutOfRange {
+                signature: signature as u128,
+                variables: N,
+            });
+        }
+        Ok(Self { signature })
+    }
+
+    pub fn signature(&self) -> u64 {
+        self.signature
+    }
+
+    /// Iterates over all machines with `N` variables, in the same order as `Machine::all`.
+    pub fn all() -> impl Iterator<Item = Self> {
+        let max = u64::MAX
+            .checked_shr(64 - Self::LAYOUT.len as u32)
+            .unwrap_or(0);
+        (0..=max).map(|signature| Self { signature })
+    }
+
+    pub fn variables(&self) -> usize {
+        N
+    }
+
+    pub fn flip(&mut self, variable: usize) {
+        assert!(
+            variable < N,
+            "variable {variable} is out of range for a machine with {N} variables"
+        );
+        self.toggle(variable);
+        // tails never contain `variable`, so no bit read here is changed by this loop
+        let cascade = &Self::LAYOUT.cascades[variable][..Self::LAYOUT.cascade_lens[variable]];
+        for &(term, tail) in cascade {
+            if self.bit(term) {
+                self.toggle(tail);
+            }
+        }
+    }
+
+    pub fn get(&self, variable: usize) -> bool {
+        assert!(
+            variable < N,
+            "variable {variable} is out of range for a machine with {N} variables"
+        );
+        self.bit(variable)
+    }
+
+    pub fn set(&mut self, variable: usize, value: bool) {
+        if self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    pub fn to_machine(&self) -> Machine {
+        Machine::from_signature(N, self.signature as u128).unwrap()
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        (self.signature >> (Self::LAYOUT.len - 1 - index)) & 1 == 1
+    }
+
+    fn toggle(&mut self, index: usize) {
+        self.signature ^= 1 << (Self::LAYOUT.len - 1 - index);
+    }
+}
+
+impl<const N: usize> TryFrom<&Machine> for FixedMachine<N> {
+    type Error = MachineError;
+
+    fn try_from(machine: &Machine) -> Result<Self, Self::Error> {
+        if machine.variables() != N {
+            return Err(MachineError::VariablesMismatch {
+                left: N,
+                right: machine.variables(),
+            });
+        }
+        if machine.table().is_bounded() {
+            return Err(MachineError::MaxOrderMismatch {
+                left: N,
+                right: machine.table().max_order(),
+            });
+        }
+        Ok(Self {
+            signature: machine.signature().unwrap() as u64,
+        })
+    }
+}
+
+impl<const N: usize> DifferentialSystem for FixedMachine<N> {
+    type Value = bool;
+
+    fn variables(&self) -> usize {
+        N
+    }
+
+    fn flip(&mut self, variable: usize) {
+        self.flip(variable);
+    }
+
+    fn get(&self, variable: usize) -> bool {
+        self.get(variable)
+    }
+
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, bool)> + '_ {
+        (0..Self::LAYOUT.len)
+            .filter(|&index| self.bit(index))
+            .map(|index| {
+                let (term, len) = &Self::LAYOUT.terms[index];
+                (Term::from(&term[..*len]), true)
+            })
+    }
+}
+
+impl<const N: usize> Debug for FixedMachine<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.to_machine().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FixedMachine, Machine, MachineError};
+
+    #[test]
+    fn matches_dense() {
+        let all = FixedMachine::<3>::all().collect::<Vec<_>>();
+        assert_eq!(
+            all.iter().map(FixedMachine::to_machine).collect::<Vec<_>>(),
+            Machine::all(3)
+        );
+        for machine in all {
+            for variable in 0..3 {
+                let mut dense = machine.to_machine();
+                let mut fixed = machine;
+                dense.flip(variable);
+                fixed.flip(variable);
+                assert_eq!(fixed.to_machine(), dense);
+                dense.set(0, true);
+                fixed.set(0, true);
+                assert_eq!(FixedMachine::try_from(&dense), Ok(fixed));
+            }
+        }
+    }
+
+    #[test]
+    fn four_variables() {
+        let mut fixed = FixedMachine::<4>::new(|term| matches!(term, [3, 0, 1, 2] | [0, 1, 2]));
+        let mut dense = Machine::new(4, |term| matches!(term, [3, 0, 1, 2] | [0, 1, 2]));
+        for variable in [3, 0, 2, 3, 1] {
+            fixed.flip(variable);
+            dense.flip(variable);
+            assert_eq!(fixed.to_machine(), dense);
+        }
+        assert_eq!(FixedMachine::<0>::all().count(), 1);
+        assert_eq!(
+            FixedMachine::<2>::from_signature(1 << 4),
+            Err(MachineError::SignatureOutOfRange {
+                signature: 1 << 4,
+                variables: 2
+            })
+        );
+        assert_eq!(
+            FixedMachine::<2>::try_from(&dense),
+            Err(MachineError::VariablesMismatch { left: 2, right: 4 })
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{GenericMachine, Value};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Which variables can affect which: there is an edge from `a` to `b != a` if, in some machine
+/// reachable from the analyzed one, flipping `a` changes the value of `b`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InfluenceGraph {
+    variables: usize,
+    edges: BTreeSet<(usize, usize)>,
+}
+
+impl InfluenceGraph {
+    pub fn from_machine<T: Value>(machine: &GenericMachine<T>) -> Self {
+        let variables = machine.variables();
+        let mut edges = BTreeSet::new();
+        for (state, _) in machine.reachable_states() {
+            for from in 0..variables {
+                for to in state.flip_impact(from) {
+                    if to != from {
+                        edges.insert((from, to));
+                    }
+                }
+            }
+        }
+        Self { variables, edges }
+    }
+
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// The edges `(from, to)`, in lexicographic order.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.edges.iter().copied()
+    }
+
+    pub fn influences(&self, from: usize, to: usize) -> bool {
+        self.edges.contains(&(from, to))
+    }
+
+    /// Writes the graph in Graphviz DOT format, with one node `v<variable>` per variable.
+    pub fn write_dot(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "digraph influence {{")?;
+        for variable in 0..self.variables {
+            writeln!(out, "    v{variable} [label=\"{variable}\"];")?;
+        }
+        for (from, to) in self.edges() {
+            writeln!(out, "    v{from} -> v{to};")?;
+        }
+        writeln!(out, "}}")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = Vec::new();
+        self.write_dot(&mut dot).unwrap();
+        String::from_utf8(dot).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+    use itertools::Itertools;
+
+    #[test]
+    fn chain() {
+        // flipping 0 changes 1, and flipping 1 changes 2, but nothing changes 0
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        let graph = system.influence_graph();
+        assert_eq!(graph.edges().collect_vec(), [(0, 1), (1, 2)]);
+        assert!(!graph.influences(0, 2));
+        assert_eq!(
+            graph.to_dot(),
+            "digraph influence {\n    v0 [label=\"0\"];\n    v1 [label=\"1\"];\n    \
+             v2 [label=\"2\"];\n    v0 -> v1;\n    v1 -> v2;\n}\n"
+        );
+    }
+
+    #[test]
+    fn through_reachable_states() {
+        // flipping 2 enables [0, 1], after which flipping 0 changes 1
+        let system = Machine::new(3, |term| term == [2, 0, 1]);
+        let graph = system.influence_graph();
+        assert_eq!(graph.edges().collect_vec(), [(0, 1)]);
+    }
+}
@@ -0,0 +1,296 @@
+//! Hash-consing of machines, for searches that hold many machines, most of them duplicates.
+//!
+//! An `Interner` stores every distinct machine once, as a row of a flat array of term values, and
+//! hands out `MachineId`s that are equal exactly if the machines are. Searches over interned
+//! machines then keep ids instead of machine clones.
+
+use crate::collections::HashMap;
+use crate::{GenericMachine, MachineError, PropagationStrategy, TermTable, Value};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::BuildHasher;
+
+/// The id of a machine in an `Interner`, in the order in which machines were first interned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MachineId(u32);
+
+impl MachineId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+// marks the end of a chain of machines with the same hash
+const NONE: u32 = u32::MAX;
+
+/// Interned machines over one term table. Machines are identified by their term values alone; the
+/// propagation strategy is not kept, and `machine` returns machines with the default one.
+#[derive(Clone, Debug)]
+pub struct Interner<T: Value = bool> {
+    table: Arc<TermTable>,
+    // the values of the machine with index `i` are `values[i * table.len()..][..table.len()]`
+    values: Vec<T>,
+    // the first machine with each hash, and for each machine the next one with the same hash
+    heads: HashMap<u64, u32>,
+    next: Vec<u32>,
+}
+
+impl<T: Value> Interner<T> {
+    /// An interner for machines over `variables` variables with the shared full table.
+    pub fn new(variables: usize) -> Self {
+        Self::with_table(TermTable::shared(variables))
+    }
+
+    pub fn with_table(table: Arc<TermTable>) -> Self {
+        Self {
+            table,
+            values: Vec::new(),
+            heads: HashMap::new(),
+            next: Vec::new(),
+        }
+    }
+
+    pub fn table(&self) -> &Arc<TermTable> {
+        &self.table
+    }
+
+    /// The number of distinct machines interned so far.
+    pub fn len(&self) -> usize {
+        self.next.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.next.is_empty()
+    }
+
+    /// The id of `machine`, which is interned if it is not yet.
+    ///
+    /// Fails if `machine` does not have the variables and maximal order of the interner's table.
+    pub fn intern(&mut self, machine: &GenericMachine<T>) -> Result<MachineId, MachineError> {
+        machine.check_table(&self.table)?;
+        Ok(self.intern_values(machine.values()))
+    }
+
+    /// The id of `machine` if it has been interned.
+    pub fn find(&self, machine: &GenericMachine<T>) -> Option<MachineId> {
+        machine.check_table(&self.table).ok()?;
+        self.find_values(machine.values(), self.hash(machine.values()))
+    }
+
+    /// The term values of the interned machine, in term order.
+    ///
+    /// Panics if `id` is not from this interner.
+    pub fn values(&self, id: MachineId) -> &[T] {
+        let len = self.table.len();
+        &self.values[id.index() * len..][..len]
+    }
+
+    /// Panics if `id` is not from this interner or `variable` is out of range.
+    pub fn get(&self, id: MachineId, variable: usize) -> T {
+        self.values(id)[self.table.variable_index(variable)]
+    }
+
+    /// A copy of the interned machine.
+    ///
+    /// Panics if `id` is not from this interner.
+    pub fn machine(&self, id: MachineId) -> GenericMachine<T> {
+        GenericMachine::from_parts(
+            self.table.clone(),
+            self.values(id).to_vec(),
+            PropagationStrategy::default(),
+        )
+    }
+
+    /// The id of the machine that flipping `variable` in the machine `id` gives, interning it if
+    /// needed.
+    ///
+    /// Panics if `id` is not from this interner or `variable` is out of range.
+    pub fn flip(&mut self, id: MachineId, variable: usize) -> MachineId {
+        let mut machine = self.machine(id);
+        machine.flip(variable);
+        self.intern_values(machine.values())
+    }
+
+    /// The ids of every machine reachable from `start` by a sequence of flips, including `start`,
+    /// in breadth-first order, as by `GenericMachine::reachable`.
+    ///
+    /// Panics if `start` is not from this interner.
+    pub fn reachable(&mut self, start: MachineId) -> Vec<MachineId> {
+        let mut seen = vec![false; self.len()];
+        seen[start.index()] = true;
+        let mut order = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            for variable in 0..self.table.variables() {
+                let next = self.flip(id, variable);
+                if next.index() >= seen.len() {
+                    seen.resize(next.index() + 1, false);
+                }
+                if !seen[next.index()] {
+                    seen[next.index()] = true;
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// The connected components of the relation "one machine becomes the other by a single
+    /// `flip`" among the machines reachable from `ids`, as by `classify` but for any set of
+    /// starting machines. Classes are ordered by their first member, and members by id.
+    ///
+    /// Panics if an id is not from this interner.
+    pub fn flip_equivalence_classes(&mut self, ids: &[MachineId]) -> Vec<Vec<MachineId>> {
+        let mut parents = Vec::new();
+        let mut queue = VecDeque::new();
+        let mut seen = Vec::new();
+        for &id in ids {
+            mark(&mut seen, id, &mut queue);
+        }
+        while let Some(id) = queue.pop_front() {
+            for variable in 0..self.table.variables() {
+                let next = self.flip(id, variable);
+                mark(&mut seen, next, &mut queue);
+                union(&mut parents, id.index(), next.index());
+            }
+        }
+        let mut class_of_root = HashMap::new();
+        let mut classes = Vec::<Vec<MachineId>>::new();
+        for index in (0..seen.len()).filter(|&index| seen[index]) {
+            let root = find(&mut parents, index);
+            let class = *class_of_root.entry(root).or_insert_with(|| {
+                classes.push(Vec::new());
+                classes.len() - 1
+            });
+            classes[class].push(MachineId(index as u32));
+        }
+        classes
+    }
+
+    fn intern_values(&mut self, values: &[T]) -> MachineId {
+        let hash = self.hash(values);
+        if let Some(id) = self.find_values(values, hash) {
+            return id;
+        }
+        let index = u32::try_from(self.len())
+            .ok()
+            .filter(|&index| index != NONE)
+            .expect("too many machines to intern");
+        let head = self.heads.entry(hash).or_insert(NONE);
+        self.next.push(*head);
+        *head = index;
+        self.values.extend_from_slice(values);
+        MachineId(index)
+    }
+
+    fn find_values(&self, values: &[T], hash: u64) -> Option<MachineId> {
+        let mut index = *self.heads.get(&hash)?;
+        while index != NONE {
+            let id = MachineId(index);
+            if self.values(id) == values {
+                return Some(id);
+            }
+            index = self.next[id.index()];
+        }
+        None
+    }
+
+    fn hash(&self, values: &[T]) -> u64 {
+        self.heads.hasher().hash_one(values)
+    }
+}
+
+// marks `id` as seen, queueing it if it was not yet
+fn mark(seen: &mut Vec<bool>, id: MachineId, queue: &mut VecDeque<MachineId>) {
+    if id.index() >= seen.len() {
+        seen.resize(id.index() + 1, false);
+    }
+    if !seen[id.index()] {
+        seen[id.index()] = true;
+        queue.push_back(id);
+    }
+}
+
+// a union-find over machine indices, grown on demand
+fn find(parents: &mut Vec<usize>, index: usize) -> usize {
+    if index >= parents.len() {
+        parents.extend(parents.len()..=index);
+    }
+    let mut element = index;
+    while parents[element] != element {
+        let grandparent = parents[parents[element]];
+        parents[element] = grandparent;
+        element = grandparent;
+    }
+    element
+}
+
+fn union(parents: &mut Vec<usize>, a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    parents[a.max(b)] = a.min(b);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intern::Interner;
+    use crate::{Machine, MachineError, classify};
+    use itertools::Itertools;
+
+    #[test]
+    fn hash_consing() {
+        let mut interner = Interner::new(2);
+        let machine = Machine::new(2, |term| term == [0, 1]);
+        let id = interner.intern(&machine).unwrap();
+        assert_eq!(interner.intern(&machine.clone()).unwrap(), id);
+        assert_eq!(interner.len(), 1);
+        assert_eq!(interner.machine(id), machine);
+        assert_eq!(interner.find(&Machine::new(2, |_| false)), None);
+
+        let flipped = interner.flip(id, 0);
+        assert_ne!(flipped, id);
+        assert!(interner.get(flipped, 0) && interner.get(flipped, 1));
+        assert_eq!(interner.flip(id, 0), flipped);
+        assert_eq!(interner.len(), 2);
+
+        assert_eq!(
+            interner.intern(&Machine::new(3, |_| false)),
+            Err(MachineError::VariablesMismatch { left: 3, right: 2 })
+        );
+    }
+
+    #[test]
+    fn reachable() {
+        let mut interner = Interner::new(3);
+        for machine in Machine::all(3).into_iter().step_by(997) {
+            let start = interner.intern(&machine).unwrap();
+            let ids = interner.reachable(start);
+            let machines = ids.iter().map(|&id| interner.machine(id)).collect_vec();
+            assert_eq!(machines, machine.reachable());
+        }
+    }
+
+    #[test]
+    fn classes() {
+        let mut interner = Interner::new(2);
+        let ids = Machine::all(2)
+            .iter()
+            .map(|machine| interner.intern(machine).unwrap())
+            .collect_vec();
+        let classes = interner.flip_equivalence_classes(&ids);
+        let expected = classify(2);
+        assert_eq!(classes.len(), expected.len());
+        for class in &classes {
+            let machines = class.iter().map(|&id| interner.machine(id)).collect_vec();
+            assert!(
+                machines
+                    .iter()
+                    .map(|machine| expected.class_of(machine).unwrap())
+                    .all_equal()
+            );
+        }
+        assert_eq!(interner.len(), ids.len());
+    }
+}
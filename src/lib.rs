@@ -1,316 +1,3327 @@
 #![allow(unused)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+pub mod analysis;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod check;
+pub mod classes;
+pub mod counting;
+mod diff;
+mod fixed;
+#[cfg(feature = "std")]
+pub mod influence;
+pub mod intern;
+mod modular;
+mod named;
+pub mod network;
+pub mod observe;
+mod packed;
+pub mod pretty;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "varisat")]
+pub mod sat;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod simulate;
+mod snapshot;
+mod sparse;
+#[cfg(feature = "std")]
+pub mod state_graph;
+mod subset;
+pub mod synthesis;
+pub mod system;
+pub mod terms;
+#[cfg(feature = "rand")]
+pub mod testing;
+pub mod text;
+#[cfg(feature = "std")]
+pub mod trace;
+mod transformation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use classes::{EquivalenceClasses, classify};
+pub use diff::{MachineDiff, TermChange};
+pub use fixed::FixedMachine;
+#[cfg(feature = "std")]
+pub use influence::InfluenceGraph;
+pub use intern::{Interner, MachineId};
+pub use modular::Modular;
+pub use named::NamedMachine;
+pub use observe::ObservedMachine;
+pub use packed::PackedMachine;
+pub use snapshot::Snapshot;
+pub use sparse::SparseMachine;
+pub use subset::SubsetMachine;
+pub use system::DifferentialSystem;
+pub use terms::{Term, TermTable, TermTables};
+pub use transformation::Transformation;
+
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use collections::{HashMap, HashSet};
+use core::cmp::Ordering;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use core::hash::Hash;
+use core::ops::{Deref, DerefMut};
+use itertools::Either;
 use itertools::Itertools;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::fmt::{Debug, Formatter};
-
-thread_local! {
-    static INDEX_TO_TERM: RefCell<HashMap<usize, Vec<Vec<usize>>>> = RefCell::new(HashMap::new());
-    static TERM_TO_INDEX: RefCell<HashMap<usize, HashMap<Vec<usize>, usize>>> = RefCell::new(HashMap::new());
-}
-
-fn index_to_term<R>(variables: usize, consumer: impl FnOnce(&Vec<Vec<usize>>) -> R) -> R {
-    INDEX_TO_TERM.with(|mut caches| {
-        let mut caches = caches.borrow_mut();
-        let cache = caches.entry(variables).or_insert_with(|| {
-            (1..=variables)
-                .flat_map(|len| (0..variables).permutations(len))
-                .collect()
-        });
-        consumer(cache)
-    })
+
+// hash maps and sets from `std` or, without it, from `hashbrown`
+mod collections {
+    #[cfg(not(feature = "std"))]
+    pub(crate) type HashMap<K, V> = hashbrown::HashMap<K, V>;
+    #[cfg(not(feature = "std"))]
+    pub(crate) type HashSet<T> = hashbrown::HashSet<T>;
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{HashMap, HashSet};
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MachineError {
+    VariablesMismatch {
+        left: usize,
+        right: usize,
+    },
+    WrongValueCount {
+        expected: usize,
+        actual: usize,
+    },
+    VariableOutOfRange {
+        variable: usize,
+        variables: usize,
+    },
+    MalformedTerm(Vec<usize>),
+    SignatureOutOfRange {
+        signature: u128,
+        variables: usize,
+    },
+    DuplicateTerm(Vec<usize>),
+    MaxOrderMismatch {
+        left: usize,
+        right: usize,
+    },
+    TermBeyondMaxOrder {
+        term: Vec<usize>,
+        max_order: usize,
+    },
+    NameCountMismatch {
+        names: usize,
+        variables: usize,
+    },
+    /// A variable name is empty or contains whitespace, `.`, `,` or `:`.
+    InvalidName(String),
+    DuplicateName(String),
+    /// A term's value differs from that of a reordering of its variables.
+    AsymmetricTerm(Vec<usize>),
+}
+
+/// The error returned by `MachineBuilder::build`.
+pub type BuildError = MachineError;
+
+impl Display for MachineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MachineError::VariablesMismatch { left, right } => {
+                write!(
+                    f,
+                    "machines have different variable counts ({left} and {right})"
+                )
+            }
+            MachineError::WrongValueCount { expected, actual } => {
+                write!(f, "expected {expected} term values, found {actual}")
+            }
+            MachineError::VariableOutOfRange {
+                variable,
+                variables,
+            } => {
+                write!(
+                    f,
+                    "variable {variable} is out of range for a machine with {variables} variables"
+                )
+            }
+            MachineError::MalformedTerm(term) => {
+                write!(
+                    f,
+                    "term {term:?} is not a non-empty sequence of distinct variables"
+                )
+            }
+            MachineError::SignatureOutOfRange {
+                signature,
+                variables,
+            } => {
+                write!(
+                    f,
+                    "signature {signature} is too large for a machine with {variables} variables"
+                )
+            }
+            MachineError::DuplicateTerm(term) => {
+                write!(f, "term {term:?} is assigned more than once")
+            }
+            MachineError::MaxOrderMismatch { left, right } => {
+                write!(
+                    f,
+                    "machines store terms of different maximal orders ({left} and {right})"
+                )
+            }
+            MachineError::TermBeyondMaxOrder { term, max_order } => {
+                write!(
+                    f,
+                    "term {term:?} is longer than the machine's maximal order {max_order}"
+                )
+            }
+            MachineError::NameCountMismatch { names, variables } => {
+                write!(f, "expected {variables} variable names, found {names}")
+            }
+            MachineError::InvalidName(name) => {
+                write!(f, "{name:?} is not a valid variable name")
+            }
+            MachineError::DuplicateName(name) => {
+                write!(f, "variable name {name:?} is given more than once")
+            }
+            MachineError::AsymmetricTerm(term) => {
+                write!(
+                    f,
+                    "term {term:?} differs from a reordering of its variables"
+                )
+            }
+        }
+    }
+}
+
+impl Error for MachineError {}
+
+/// The values a machine's terms can take. `flip` adds `ONE` to the flipped variable and then adds
+/// the value of every term `[variable, rest..]` to the term `[rest..]`, so for `bool` (GF(2)) with
+/// `add` being XOR this is the original toggle cascade. `Modular` provides the integers modulo `k`.
+pub trait Value: Copy + Eq + Ord + Hash + Debug {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn add(self, other: Self) -> Self;
+}
+
+impl Value for bool {
+    const ZERO: Self = false;
+    const ONE: Self = true;
+
+    fn add(self, other: Self) -> Self {
+        self ^ other
+    }
+}
+
+/// How a machine applies the changes that make up its flips.
+///
+/// A single `flip` of `v` adds `ONE` to `v` and the value of every term `[v, rest..]` to the term
+/// `[rest..]`. Every term read starts with `v` and every term written does not contain `v`, so no
+/// change can affect another one and all strategies give the same values; they only differ in the
+/// order in which the changes are made, as seen by `flip_traced` and observers. Where changes do
+/// affect each other, as when `flip_many` flips several variables or a round of `flip_cascading`
+/// triggers several, the strategy decides the resulting machine.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum PropagationStrategy {
+    /// Every change is computed from the values before any of them is made, and the changes are
+    /// then added up. Changes are made in ascending term order.
+    #[default]
+    Simultaneous,
+    /// Changes are made one after the other in ascending term order, each seeing the ones before
+    /// it: `flip` goes from the flipped variable to the longest terms, and `flip_many` flips the
+    /// variables in ascending order.
+    Ascending,
+    /// Like `Ascending`, but in descending term order.
+    Descending,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct GenericMachine<T: Value> {
+    table: Arc<TermTable>,
+    values: Vec<T>,
+    // bit `v` is set if variable `v` is nonzero, for the first 64 variables; every write to
+    // `values` goes through `store` or is followed by `sync_observables` to keep it up to date
+    observables: u64,
+    strategy: PropagationStrategy,
 }
 
-fn term_to_index<R>(
-    variables: usize,
-    consumer: impl FnOnce(&HashMap<Vec<usize>, usize>) -> R,
-) -> R {
-    TERM_TO_INDEX.with(|mut caches| {
-        let mut caches = caches.borrow_mut();
-        let cache = caches.entry(variables).or_insert_with(|| {
-            index_to_term(variables, |index_to_term| {
-                index_to_term
-                    .iter()
-                    .cloned()
-                    .enumerate()
-                    .map(|(index, term)| (term, index))
-                    .collect()
-            })
+pub type Machine = GenericMachine<bool>;
+
+// sets `values[index]` to `value` and keeps bit `index` of `observables` up to date, which has bits
+// for the first 64 of the `variables` single-variable terms at the front of `values`
+fn store<T: Value>(
+    values: &mut [T],
+    observables: &mut u64,
+    variables: usize,
+    index: usize,
+    value: T,
+) {
+    values[index] = value;
+    if index < 64 && index < variables {
+        if value != T::ZERO {
+            *observables |= 1 << index;
+        } else {
+            *observables &= !(1 << index);
+        }
+    }
+}
+
+impl<T: Value> GenericMachine<T> {
+    pub fn new(variables: usize, initial_values_producer: impl FnMut(&[usize]) -> T) -> Self {
+        Self::with_table(TermTable::shared(variables), initial_values_producer)
+    }
+
+    /// Like `new`, but only has the terms of at most `max_order` variables, as if all longer terms
+    /// were zero. These are never stored, so `initial_values_producer` is not called for them.
+    /// `max_order` is clamped as by `TermTable::bounded`.
+    pub fn new_bounded(
+        variables: usize,
+        max_order: usize,
+        initial_values_producer: impl FnMut(&[usize]) -> T,
+    ) -> Self {
+        Self::with_table(
+            TermTable::shared_bounded(variables, max_order),
+            initial_values_producer,
+        )
+    }
+
+    /// Like `new`, but uses the given term table instead of the shared one.
+    pub fn with_table(
+        table: Arc<TermTable>,
+        mut initial_values_producer: impl FnMut(&[usize]) -> T,
+    ) -> Self {
+        let values = table
+            .terms()
+            .iter()
+            .map(|term| initial_values_producer(term))
+            .collect();
+        Self::from_parts(table, values, PropagationStrategy::default())
+    }
+
+    fn from_parts(table: Arc<TermTable>, values: Vec<T>, strategy: PropagationStrategy) -> Self {
+        let mut machine = Self {
+            table,
+            values,
+            observables: 0,
+            strategy,
+        };
+        machine.sync_observables();
+        machine
+    }
+
+    // recomputes `observables` after `values` changed in bulk
+    fn sync_observables(&mut self) {
+        let variables = self.variables().min(64);
+        self.observables = (self.values.iter().take(variables).enumerate())
+            .filter(|&(_, &value)| value != T::ZERO)
+            .fold(0, |mask, (variable, _)| mask | 1 << variable);
+    }
+
+    // sets the term at `index` to `value` and keeps `observables` up to date
+    fn store(&mut self, index: usize, value: T) {
+        let variables = self.variables();
+        store(
+            &mut self.values,
+            &mut self.observables,
+            variables,
+            index,
+            value,
+        );
+    }
+
+    /// Creates a machine from its term values, given in the crate's internal term order.
+    pub fn from_values(variables: usize, values: Vec<T>) -> Result<Self, MachineError> {
+        let machine = Self::from_parts(
+            TermTable::shared(variables),
+            values,
+            PropagationStrategy::default(),
+        );
+        machine.validate()?;
+        Ok(machine)
+    }
+
+    pub fn variables(&self) -> usize {
+        self.table.variables()
+    }
+
+    pub fn table(&self) -> &Arc<TermTable> {
+        &self.table
+    }
+
+    /// The term values, in the crate's internal term order.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn strategy(&self) -> PropagationStrategy {
+        self.strategy
+    }
+
+    /// Changes how later flips are applied; see `PropagationStrategy`.
+    pub fn set_strategy(&mut self, strategy: PropagationStrategy) {
+        self.strategy = strategy;
+    }
+
+    pub fn with_strategy(mut self, strategy: PropagationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn builder(variables: usize) -> MachineBuilder<T> {
+        MachineBuilder::new(variables)
+    }
+
+    /// Checks the structural invariants the other methods rely on, namely that there is exactly
+    /// one value per term.
+    pub fn validate(&self) -> Result<(), MachineError> {
+        let expected = self.table.len();
+        if self.values.len() != expected {
+            return Err(MachineError::WrongValueCount {
+                expected,
+                actual: self.values.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Like `new`, but the returned machine keeps a copy of its initial values so that it can be
+    /// reset to them later.
+    pub fn new_resettable(
+        variables: usize,
+        initial_values_producer: impl FnMut(&[usize]) -> T,
+    ) -> ResettableMachine<T> {
+        Self::new(variables, initial_values_producer).into_resettable()
+    }
+
+    pub fn into_resettable(self) -> ResettableMachine<T> {
+        ResettableMachine {
+            initial_values: self.values.clone(),
+            machine: self,
+        }
+    }
+
+    /// Adds `T::ONE` to `variable` and then, for every term `[variable, rest..]`, adds its value to
+    /// the term `[rest..]`. The resulting values do not depend on the machine's
+    /// `PropagationStrategy`.
+    ///
+    /// Panics if `variable >= self.variables()`; see `try_flip` for a checked version.
+    pub fn flip(&mut self, variable: usize) {
+        self.flip_observed(variable, |_, _| {});
+    }
+
+    pub fn try_flip(&mut self, variable: usize) -> Result<(), MachineError> {
+        self.check_variable(variable)?;
+        self.flip(variable);
+        Ok(())
+    }
+
+    /// Flips all of `variables` according to the machine's `PropagationStrategy`. With the default
+    /// `Simultaneous` strategy, every flip and its cascade is computed from the values before any of
+    /// them is applied, and the resulting changes are then added up, so that unlike flipping the
+    /// variables one after the other, no flip can observe another one's effects. The `Ascending`
+    /// and `Descending` strategies flip the variables one after the other, sorted accordingly.
+    /// Listing a variable `k` times flips it `k` times.
+    ///
+    /// Panics if any variable is out of range.
+    pub fn flip_many(&mut self, variables: &[usize]) {
+        if self.strategy != PropagationStrategy::Simultaneous {
+            let mut variables = variables.to_vec();
+            variables.sort_unstable();
+            if self.strategy == PropagationStrategy::Descending {
+                variables.reverse();
+            }
+            for variable in variables {
+                self.flip(variable);
+            }
+            return;
+        }
+        let mut changes = Vec::new();
+        for &variable in variables {
+            changes.push((variable, T::ONE));
+            self.cascade_changes(variable, &mut changes);
+        }
+        self.apply_changes(changes);
+    }
+
+    /// Flips `variable` like `flip` and then keeps propagating, in rounds: every variable whose
+    /// value the previous round changed triggers its terms, adding the value of every term
+    /// `[v, rest..]` to `[rest..]` as its flip would, but without changing `v` itself again. This
+    /// may change further variables, which trigger their terms in the next round. The first round
+    /// triggers the variables that the flip of `variable` changed.
+    ///
+    /// With the `Simultaneous` strategy, a round's changes are all computed from the values before
+    /// the round; with `Ascending` and `Descending`, the round's variables trigger their terms one
+    /// after the other in that order, each seeing the changes of the ones before it.
+    ///
+    /// Stops once a round changes no variable, once the machine returns to an earlier state with
+    /// the same variables left to trigger, or after `max_rounds` rounds, in which case the machine
+    /// is left as it is then.
+    ///
+    /// Panics if `variable >= self.variables()`.
+    pub fn flip_cascading(&mut self, variable: usize, max_rounds: usize) -> CascadeOutcome {
+        let mut before = self.values[..self.variables()].to_vec();
+        self.flip(variable);
+        let mut seen = HashMap::new();
+        for round in 0.. {
+            let triggered = (0..self.variables())
+                .filter(|&other| other != variable || round > 0)
+                .filter(|&other| self.values[other] != before[other])
+                .collect_vec();
+            if triggered.is_empty() {
+                return CascadeOutcome::Settled { rounds: round };
+            }
+            if round == max_rounds {
+                return CascadeOutcome::Unsettled;
+            }
+            if let Some(earlier) = seen.insert((self.values.clone(), triggered.clone()), round) {
+                return CascadeOutcome::Oscillation {
+                    period: round - earlier,
+                };
+            }
+            before.copy_from_slice(&self.values[..self.variables()]);
+            match self.strategy {
+                PropagationStrategy::Simultaneous => {
+                    let mut changes = Vec::new();
+                    for &variable in &triggered {
+                        self.cascade_changes(variable, &mut changes);
+                    }
+                    self.apply_changes(changes);
+                }
+                PropagationStrategy::Ascending => {
+                    for &variable in &triggered {
+                        let mut changes = Vec::new();
+                        self.cascade_changes(variable, &mut changes);
+                        self.apply_changes(changes);
+                    }
+                }
+                PropagationStrategy::Descending => {
+                    for &variable in triggered.iter().rev() {
+                        let mut changes = Vec::new();
+                        self.cascade_changes(variable, &mut changes);
+                        self.apply_changes(changes);
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+
+    // pushes the change that `variable`'s flip makes to the tail of each of its terms
+    fn cascade_changes(&self, variable: usize, changes: &mut Vec<(usize, T)>) {
+        for &(term, tail) in &self.table.cascade(variable).terms {
+            let delta = self.values[term];
+            if delta != T::ZERO {
+                changes.push((tail, delta));
+            }
+        }
+    }
+
+    fn apply_changes(&mut self, changes: Vec<(usize, T)>) {
+        for (index, delta) in changes {
+            self.store(index, self.values[index].add(delta));
+        }
+    }
+
+    /// Wraps this machine so that its flips are recorded and can be undone and redone.
+    pub fn into_recorded(self) -> RecordedMachine<T> {
+        RecordedMachine {
+            machine: self,
+            flips: Vec::new(),
+            previous_values: Vec::new(),
+            applied: 0,
+        }
+    }
+
+    /// Wraps this machine so that callbacks can be registered that are called whenever a flip
+    /// changes a variable or term.
+    pub fn into_observed<'a>(self) -> ObservedMachine<'a, T> {
+        ObservedMachine::new(self)
+    }
+
+    /// Flips `variable` like `flip` and returns, in order, every term whose value was changed.
+    pub fn flip_traced(&mut self, variable: usize) -> Vec<Vec<usize>> {
+        let mut trace = Vec::new();
+        self.flip_observed(variable, |term, _| trace.push(term.to_vec()));
+        trace
+    }
+
+    // calls `changed` with every changed term and its new value, in the order of the strategy
+    fn flip_observed(&mut self, variable: usize, mut changed: impl FnMut(&Term, T)) {
+        let Self {
+            table,
+            values,
+            observables,
+            strategy,
+        } = self;
+        let variables = table.variables();
+        let cascade = table.cascade(variable);
+        let descending = *strategy == PropagationStrategy::Descending;
+        if !descending {
+            let value = values[variable].add(T::ONE);
+            store(values, observables, variables, variable, value);
+            changed(table.term(variable), value);
+        }
+        // tails never contain `variable`, so no term read here is changed by this loop, and the
+        // order only matters to `changed`
+        let corrections = if descending {
+            Either::Left(cascade.terms.iter().rev())
+        } else {
+            Either::Right(cascade.terms.iter())
+        };
+        for &(term, tail) in corrections {
+            let delta = values[term];
+            if delta != T::ZERO {
+                let value = values[tail].add(delta);
+                store(values, observables, variables, tail, value);
+                changed(table.term(tail), value);
+            }
+        }
+        if descending {
+            let value = values[variable].add(T::ONE);
+            store(values, observables, variables, variable, value);
+            changed(table.term(variable), value);
+        }
+    }
+
+    /// Reads the value of the term `[variable]`, which is stored at index `variable`, so this takes
+    /// constant time.
+    ///
+    /// Panics if `variable >= self.variables()`; see `try_get` for a checked version.
+    pub fn get(&self, variable: usize) -> T {
+        self.values[self.table.variable_index(variable)]
+    }
+
+    pub fn try_get(&self, variable: usize) -> Result<T, MachineError> {
+        self.check_variable(variable)?;
+        Ok(self.get(variable))
+    }
+
+    fn check_variable(&self, variable: usize) -> Result<(), MachineError> {
+        if variable >= self.variables() {
+            return Err(MachineError::VariableOutOfRange {
+                variable,
+                variables: self.variables(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the value of an arbitrary term, which is zero for terms beyond the maximal order of
+    /// a bounded machine.
+    ///
+    /// Panics if `term` is not a term over this machine's variables.
+    pub fn get_term(&self, term: &Term) -> T {
+        self.value_of(term)
+    }
+
+    fn value_of(&self, term: &[usize]) -> T {
+        if term.len() > self.table.max_order() && terms::rank(self.variables(), term).is_some() {
+            return T::ZERO;
+        }
+        self.values[self.table.expect_index(term)]
+    }
+
+    /// Overwrites the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term of this machine, which includes terms beyond the maximal
+    /// order of a bounded machine.
+    pub fn set_term(&mut self, term: &Term, value: T) {
+        let index = self.table.expect_index(term);
+        self.store(index, value);
+    }
+
+    /// Adds `T::ONE` to the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term of this machine.
+    pub fn flip_term(&mut self, term: &Term) {
+        let index = self.table.expect_index(term);
+        self.store(index, self.values[index].add(T::ONE));
+    }
+
+    /// Sets every term of more than `max_order` variables to zero. The terms stay stored; see
+    /// `new_bounded` for machines without them.
+    pub fn truncate(&mut self, max_order: usize) {
+        for (term, value) in self.table.terms().iter().zip(&mut self.values) {
+            if term.len() > max_order {
+                *value = T::ZERO;
+            }
+        }
+        self.sync_observables();
+    }
+
+    /// Iterates over all terms and their values, in the crate's internal term order.
+    pub fn terms(&self) -> impl Iterator<Item = (&Term, T)> {
+        self.table.terms().iter().zip(self.values.iter().copied())
+    }
+
+    /// Whether every term of two or more variables is zero, so that flips never cascade.
+    pub fn is_linear(&self) -> bool {
+        self.max_order() <= 1
+    }
+
+    /// Whether every term has the same value as each reordering of its variables.
+    pub fn is_symmetric(&self) -> bool {
+        self.terms().all(|(term, value)| {
+            let sorted = term.iter().copied().sorted().collect_vec();
+            self.values[self.table.expect_index(&sorted)] == value
+        })
+    }
+
+    /// The length of the longest nonzero term, or 0 if all terms are zero.
+    pub fn max_order(&self) -> usize {
+        self.terms()
+            .filter(|&(_, value)| value != T::ZERO)
+            .map(|(term, _)| term.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Flips `variable` until it has the given value. For value types in which repeatedly adding
+    /// `ONE` never reaches `value`, this does not terminate.
+    ///
+    /// Panics if `variable >= self.variables()`; see `try_set` for a checked version.
+    pub fn set(&mut self, variable: usize, value: T) {
+        while self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    pub fn try_set(&mut self, variable: usize, value: T) -> Result<(), MachineError> {
+        self.check_variable(variable)?;
+        self.set(variable, value);
+        Ok(())
+    }
+
+    /// Returns every machine reachable from `self` by a sequence of flips, including `self`, in
+    /// breadth-first order.
+    pub fn reachable(&self) -> Vec<Self> {
+        system::reachable(self)
+    }
+
+    /// Lazily visits every machine reachable from `self` in breadth-first order, together with a
+    /// shortest sequence of variables to flip to get there from `self`.
+    pub fn reachable_states(&self) -> ReachableStates<T> {
+        ReachableStates {
+            queue: VecDeque::from([(self.clone(), Vec::new())]),
+            seen: HashSet::from([self.clone()]),
+        }
+    }
+
+    /// A shortest sequence of flips after which every variable `v` with `target[v]` given has that
+    /// value, or `None` if no reachable machine has them.
+    ///
+    /// Panics if `target.len() != self.variables()`.
+    pub fn solve(&self, target: &[Option<T>]) -> Option<Vec<usize>> {
+        assert_eq!(
+            target.len(),
+            self.variables(),
+            "the target must give one entry per variable"
+        );
+        self.reachable_states()
+            .find(|(machine, _)| {
+                target.iter().enumerate().all(|(variable, &value)| {
+                    value.is_none_or(|value| machine.get(variable) == value)
+                })
+            })
+            .map(|(_, flips)| flips)
+    }
+
+    /// Like `reachable_states().find(..)`: a shortest sequence of flips to a machine satisfying
+    /// `predicate`, the same one `reachable_states` would find first, or `None` if no reachable
+    /// machine does. Each breadth-first level is expanded and tested in parallel on rayon's thread
+    /// pool. All machines share their term table through its `Arc`, so tables are not duplicated
+    /// per thread.
+    #[cfg(feature = "rayon")]
+    pub fn par_search(&self, predicate: impl Fn(&Self) -> bool + Sync) -> Option<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let mut seen = HashSet::from([self.clone()]);
+        let mut level = vec![(self.clone(), Vec::new())];
+        while !level.is_empty() {
+            if let Some(found) = level
+                .par_iter()
+                .position_first(|(machine, _)| predicate(machine))
+            {
+                return Some(level.swap_remove(found).1);
+            }
+            // collected in order, so the first path to each machine is the one a sequential
+            // breadth-first search would take
+            let successors = level
+                .par_iter()
+                .flat_map_iter(|(machine, flips)| {
+                    (0..machine.variables()).map(move |variable| {
+                        let mut successor = machine.clone();
+                        successor.flip(variable);
+                        let mut flips = flips.clone();
+                        flips.push(variable);
+                        (successor, flips)
+                    })
+                })
+                .collect::<Vec<_>>();
+            level = successors
+                .into_iter()
+                .filter(|(machine, _)| seen.insert(machine.clone()))
+                .collect();
+        }
+        None
+    }
+
+    /// Repeatedly applies `set(variable, value)` to a clone of `self`, for at most `max_steps` steps,
+    /// and reports how the sequence of machines evolves.
+    pub fn orbit(&self, variable: usize, value: T, max_steps: usize) -> Orbit<T> {
+        Orbit::explore(self, max_steps, |machine| machine.set(variable, value))
+    }
+
+    /// Finds the cycle that repeatedly flipping the variables in `sequence`, one after the other,
+    /// eventually enters, using Brent's algorithm. Since the state space is finite, the outcome is
+    /// always a fixed point or a cycle.
+    pub fn sequence_orbit(&self, sequence: &[usize]) -> Orbit<T> {
+        let step = |machine: &Self| {
+            let mut next = machine.clone();
+            for &variable in sequence {
+                next.flip(variable);
+            }
+            next
+        };
+
+        let mut power = 1;
+        let mut length = 1;
+        let mut tortoise = self.clone();
+        let mut hare = step(self);
+        while tortoise != hare {
+            if power == length {
+                tortoise = hare.clone();
+                power *= 2;
+                length = 0;
+            }
+            hare = step(&hare);
+            length += 1;
+        }
+
+        let mut tortoise = self.clone();
+        let mut hare = self.clone();
+        for _ in 0..length {
+            hare = step(&hare);
+        }
+        let mut transient = 0;
+        while tortoise != hare {
+            tortoise = step(&tortoise);
+            hare = step(&hare);
+            transient += 1;
+        }
+
+        let visited = core::iter::successors(Some(self.clone()), |machine| Some(step(machine)))
+            .take(transient + length)
+            .collect();
+        let outcome = match length {
+            1 => OrbitOutcome::FixedPoint,
+            length => OrbitOutcome::Cycle { length },
+        };
+        Orbit { visited, outcome }
+    }
+
+    /// Checks whether flipping `variable` twice restores `self`. This holds for every `bool`
+    /// machine, since the terms that drive the cascade start with `variable` and are never changed
+    /// by it, but not in general for other value types.
+    pub fn flip_is_involution(&self, variable: usize) -> bool {
+        let mut machine = self.clone();
+        machine.flip(variable);
+        machine.flip(variable);
+        machine == *self
+    }
+
+    /// Returns the variables whose value would change if `variable` were flipped, without
+    /// modifying `self`.
+    pub fn flip_impact(&self, variable: usize) -> BTreeSet<usize> {
+        let mut scratch = self.clone();
+        scratch.flip(variable);
+        (0..self.variables())
+            .filter(|&other| scratch.get(other) != self.get(other))
+            .collect()
+    }
+
+    /// Finds the variables and higher-order terms that keep their current value in every machine
+    /// reachable from `self`. Since flipping a variable always changes it, a variable can only be
+    /// constant if `T::ONE` is zero.
+    pub fn invariants(&self) -> Invariants<T> {
+        let mut constant = vec![true; self.values.len()];
+        for (machine, _) in self.reachable_states() {
+            for (constant, (old, new)) in constant
+                .iter_mut()
+                .zip(self.values.iter().zip(&machine.values))
+            {
+                *constant &= old == new;
+            }
+        }
+        let mut invariants = Invariants {
+            constant_variables: Vec::new(),
+            constant_terms: Vec::new(),
+        };
+        for (index, (term, value)) in self.terms().enumerate() {
+            if !constant[index] {
+                continue;
+            }
+            if term.len() == 1 {
+                invariants.constant_variables.push(term[0]);
+            } else {
+                invariants.constant_terms.push((term.clone(), value));
+            }
+        }
+        invariants
+    }
+
+    /// Whether no sequence of flips, applied to both machines, ever makes some variable differ
+    /// between them.
+    pub fn behaviorally_equivalent(&self, other: &Self) -> bool {
+        self.distinguishing_sequence(other).is_none()
+    }
+
+    /// A shortest sequence of flips after which some variable differs between `self` and `other`
+    /// when applied to both, or `None` if there is none. Machines with different numbers of
+    /// variables are distinguished by the empty sequence.
+    pub fn distinguishing_sequence(&self, other: &Self) -> Option<Vec<usize>> {
+        let observables = |machine: &Self| {
+            (0..machine.variables())
+                .map(|v| machine.get(v))
+                .collect_vec()
+        };
+        if self.variables() != other.variables() {
+            return Some(Vec::new());
+        }
+        let start = (self.clone(), other.clone());
+        let mut seen = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([(start, Vec::new())]);
+        while let Some(((left, right), flips)) = queue.pop_front() {
+            if observables(&left) != observables(&right) {
+                return Some(flips);
+            }
+            for variable in 0..self.variables() {
+                let (mut left, mut right) = (left.clone(), right.clone());
+                left.flip(variable);
+                right.flip(variable);
+                if seen.insert((left.clone(), right.clone())) {
+                    let mut flips = flips.clone();
+                    flips.push(variable);
+                    queue.push_back(((left, right), flips));
+                }
+            }
+        }
+        None
+    }
+
+    /// The smallest machine behaviorally equivalent to `self`, which is `self` itself: flipping `a`
+    /// adds the value of each term `[a, rest..]` to `[rest..]`, so by induction on the length of
+    /// terms, every term's value can be read off some sequence of flips. To pick one machine per
+    /// class of machines reachable from each other, use `classify` and its representatives.
+    pub fn minimize(&self) -> Self {
+        self.clone()
+    }
+
+    /// Checks `property` against the machines reachable from `self`; see `check::check`.
+    pub fn check(&self, property: &check::Property<T>) -> Result<(), check::Counterexample> {
+        check::check(self, property)
+    }
+
+    /// The explicit transition system of the machines reachable from `self`, in which each flip is
+    /// an input and the variable values are the outputs; see `StateGraph`.
+    #[cfg(feature = "std")]
+    pub fn to_fsm(&self) -> state_graph::StateGraph<Self> {
+        state_graph::StateGraph::from_machine(self)
+    }
+
+    /// Which variables can change which others when flipped; see `InfluenceGraph`.
+    #[cfg(feature = "std")]
+    pub fn influence_graph(&self) -> InfluenceGraph {
+        InfluenceGraph::from_machine(self)
+    }
+
+    /// Lists every term whose value differs between `self` and `other`, together with its value in
+    /// `self` and in `other`, in term order.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Result<MachineDiff<'a, T>, MachineError> {
+        self.check_table(other.table())?;
+        let changes = self
+            .table
+            .terms()
+            .iter()
+            .zip(self.values.iter().zip(other.values.iter()))
+            .filter(|(_, (old, new))| old != new)
+            .map(|(term, (&old, &new))| TermChange {
+                term: term.variables(),
+                old,
+                new,
+            })
+            .collect();
+        Ok(MachineDiff {
+            variables: self.variables(),
+            changes,
+        })
+    }
+
+    // fails unless `table` has the same variables and maximal order as this machine's
+    fn check_table(&self, table: &TermTable) -> Result<(), MachineError> {
+        if self.variables() != table.variables() {
+            return Err(MachineError::VariablesMismatch {
+                left: self.variables(),
+                right: table.variables(),
+            });
+        }
+        if self.table.max_order() != table.max_order() {
+            return Err(MachineError::MaxOrderMismatch {
+                left: self.table.max_order(),
+                right: table.max_order(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Combines `self` and `other` into one machine over `self.variables() + other.variables()`
+    /// variables, where variable `v` of `other` becomes variable `self.variables() + v`. Terms
+    /// mixing variables of both machines are zero, so the two halves do not influence each other.
+    ///
+    /// If either machine is bounded, so is the result, with the larger of their maximal orders.
+    pub fn disjoint_union(&self, other: &Self) -> Self {
+        self.compose_with(other, |_| T::ZERO)
+    }
+
+    /// The direct sum of `self` and `other`; the same as `disjoint_union`.
+    pub fn compose(&self, other: &Self) -> Self {
+        self.disjoint_union(other)
+    }
+
+    /// Like `disjoint_union`, but initializes each term mixing variables of both machines (in the
+    /// numbering of the result) with `cross_terms`.
+    pub fn compose_with(&self, other: &Self, mut cross_terms: impl FnMut(&[usize]) -> T) -> Self {
+        let offset = self.variables();
+        let variables = self.variables() + other.variables();
+        let max_order = if self.table.is_bounded() || other.table.is_bounded() {
+            self.table.max_order().max(other.table.max_order())
+        } else {
+            variables
+        };
+        Self::new_bounded(variables, max_order, |term| {
+            if term.iter().all(|&variable| variable < offset) {
+                self.value_of(term)
+            } else if term.iter().all(|&variable| variable >= offset) {
+                let term = term.iter().map(|&variable| variable - offset).collect_vec();
+                other.value_of(&term)
+            } else {
+                cross_terms(term)
+            }
+        })
+    }
+
+    /// Returns the machine over `keep.len()` variables in which variable `i` stands for variable
+    /// `keep[i]` of `self`. Every term made up of kept variables keeps its value; terms mentioning
+    /// any other variable are dropped, so flips in the result ignore the dropped variables. The
+    /// result has the same maximal order as `self`.
+    ///
+    /// Panics if `keep` contains duplicates or variables out of range.
+    pub fn restrict(&self, keep: &[usize]) -> Self {
+        assert!(
+            all_distinct(keep) && keep.iter().all(|&variable| variable < self.variables()),
+            "{keep:?} is not a selection of distinct variables"
+        );
+        Self::new_bounded(keep.len(), self.table.max_order(), |term| {
+            let original = term.iter().map(|&variable| keep[variable]).collect_vec();
+            self.values[self.table.expect_index(&original)]
+        })
+    }
+
+    /// Returns the lexicographically smallest machine (by term values) among all machines obtained
+    /// by relabeling the variables of `self`.
+    pub fn canonicalize(&self) -> Self {
+        self.canonical_form().0
+    }
+
+    /// Like `canonicalize`, but also returns the relabeling that turns `self` into the canonical
+    /// machine, in the sense of `permute_variables`. If several do, it is the least one.
+    pub fn canonical_form(&self) -> (Self, Vec<usize>) {
+        (0..self.variables())
+            .permutations(self.variables())
+            .map(|relabeling| (self.permute_variables(&relabeling), relabeling))
+            .min_by(|(a, _), (b, _)| a.values.cmp(&b.values))
+            .unwrap()
+    }
+
+    pub fn is_isomorphic(&self, other: &Self) -> bool {
+        self.table == other.table && self.canonicalize() == other.canonicalize()
+    }
+
+    /// Searches for a relabeling that turns `self` into `other`, in the sense of
+    /// `permute_variables`.
+    pub fn is_isomorphic_to(&self, other: &Self) -> Option<Vec<usize>> {
+        if self.check_table(other.table()).is_err() {
+            return None;
+        }
+        (0..self.variables())
+            .permutations(self.variables())
+            .find(|relabeling| {
+                relabeling_sources(&self.table, relabeling)
+                    .into_iter()
+                    .zip(other.values.iter())
+                    .all(|(source, value)| self.values[source] == *value)
+            })
+    }
+
+    /// All relabelings, in the sense of `permute_variables`, that leave `self` unchanged, in
+    /// lexicographic order. The identity is always among them.
+    pub fn automorphisms(&self) -> Vec<Vec<usize>> {
+        let completed_by = self.completed_by();
+        let mut automorphisms = Vec::new();
+        let mut relabeling = Vec::with_capacity(self.variables());
+        self.extend_automorphism(&completed_by, &mut relabeling, &mut automorphisms);
+        automorphisms
+    }
+
+    /// Searches for an injective mapping of the variables of `self` to variables of `other` under
+    /// which every term of `self`, including the single variables, has the value of its image in
+    /// `other`, so that `self` occurs in `other` as a sub-machine. Variable `v` of `self` maps to
+    /// variable `mapping[v]` of `other`, and the first mapping in lexicographic order is returned.
+    ///
+    /// As with `restrict`, only the terms among the mapped variables are compared: the terms of
+    /// `other` that mention other variables too may connect the copy of `self` to the rest of
+    /// `other` in any way.
+    pub fn embeds_in(&self, other: &Self) -> Option<Vec<usize>> {
+        let completed_by = self.completed_by();
+        let mut mapping = Vec::with_capacity(self.variables());
+        self.extend_embedding(other, &completed_by, &mut mapping)
+            .then_some(mapping)
+    }
+
+    // for each variable, the indices of the terms whose largest variable it is, which can be
+    // checked once the variables up to it have been mapped
+    fn completed_by(&self) -> Vec<Vec<usize>> {
+        let mut completed_by = vec![Vec::new(); self.variables()];
+        for (index, term) in self.table.terms().iter().enumerate() {
+            completed_by[*term.iter().max().unwrap()].push(index);
+        }
+        completed_by
+    }
+
+    fn extend_embedding(
+        &self,
+        other: &Self,
+        completed_by: &[Vec<usize>],
+        mapping: &mut Vec<usize>,
+    ) -> bool {
+        let variable = mapping.len();
+        if variable == self.variables() {
+            return true;
+        }
+        for image in 0..other.variables() {
+            if mapping.contains(&image) {
+                continue;
+            }
+            mapping.push(image);
+            let consistent = completed_by[variable].iter().all(|&index| {
+                let term = self.table.term(index);
+                let image = term.iter().map(|&variable| mapping[variable]).collect_vec();
+                other.value_of(&image) == self.values[index]
+            });
+            if consistent && self.extend_embedding(other, completed_by, mapping) {
+                return true;
+            }
+            mapping.pop();
+        }
+        false
+    }
+
+    fn extend_automorphism(
+        &self,
+        completed_by: &[Vec<usize>],
+        relabeling: &mut Vec<usize>,
+        automorphisms: &mut Vec<Vec<usize>>,
+    ) {
+        let variable = relabeling.len();
+        if variable == self.variables() {
+            automorphisms.push(relabeling.clone());
+            return;
+        }
+        for image in 0..self.variables() {
+            if relabeling.contains(&image) {
+                continue;
+            }
+            relabeling.push(image);
+            let consistent = completed_by[variable].iter().all(|&index| {
+                let term = self.table.term(index);
+                let image = term
+                    .iter()
+                    .map(|&variable| relabeling[variable])
+                    .collect_vec();
+                self.values[self.table.expect_index(&image)] == self.values[index]
+            });
+            if consistent {
+                self.extend_automorphism(completed_by, relabeling, automorphisms);
+            }
+            relabeling.pop();
+        }
+    }
+
+    /// Relabels the variables, such that variable `v` of `self` becomes variable `relabeling[v]`
+    /// of the result, and every term is renamed accordingly.
+    ///
+    /// Panics if `relabeling` is not a permutation of `0..self.variables()`.
+    pub fn permute_variables(&self, relabeling: &[usize]) -> Self {
+        assert!(
+            relabeling.len() == self.variables()
+                && all_distinct(relabeling)
+                && relabeling
+                    .iter()
+                    .all(|&variable| variable < self.variables()),
+            "{relabeling:?} is not a permutation of the variables"
+        );
+        let values = relabeling_sources(&self.table, relabeling)
+            .into_iter()
+            .map(|source| self.values[source])
+            .collect();
+        Self::from_parts(self.table.clone(), values, self.strategy)
+    }
+}
+
+// for each term index of the machine relabeled by `relabeling`, the index of the term of the
+// original machine that it takes its value from
+fn relabeling_sources(table: &TermTable, relabeling: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; table.variables()];
+    for (from, &to) in relabeling.iter().enumerate() {
+        inverse[to] = from;
+    }
+    table
+        .terms()
+        .iter()
+        .map(|term| {
+            let original = term.iter().map(|&variable| inverse[variable]).collect_vec();
+            table.expect_index(&original)
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ResettableMachine<T: Value> {
+    machine: GenericMachine<T>,
+    initial_values: Vec<T>,
+}
+
+impl<T: Value> ResettableMachine<T> {
+    pub fn reset_to_initial(&mut self) {
+        self.machine.values.clone_from(&self.initial_values);
+        self.machine.sync_observables();
+    }
+
+    pub fn into_inner(self) -> GenericMachine<T> {
+        self.machine
+    }
+}
+
+impl<T: Value> Deref for ResettableMachine<T> {
+    type Target = GenericMachine<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.machine
+    }
+}
+
+impl<T: Value> DerefMut for ResettableMachine<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.machine
+    }
+}
+
+/// A machine that records its flips. Flipping is not an involution on the whole state for every
+/// value type, so each recorded flip keeps the previous values of the terms it changed, which lets
+/// `undo` restore the exact state before it.
+///
+/// Only shared access to the machine is given out, so the record cannot be bypassed; use
+/// `into_inner` to get the machine back.
+#[derive(Clone, Debug)]
+pub struct RecordedMachine<T: Value> {
+    machine: GenericMachine<T>,
+    flips: Vec<usize>,
+    // for each recorded flip, the indices and previous values of the terms it changed
+    previous_values: Vec<Vec<(usize, T)>>,
+    // the number of recorded flips currently applied; the rest can be redone
+    applied: usize,
+}
+
+impl<T: Value> RecordedMachine<T> {
+    /// Flips `variable` and records the flip, discarding any flips that could have been redone.
+    pub fn flip_recorded(&mut self, variable: usize) {
+        self.flips.truncate(self.applied);
+        self.previous_values.truncate(self.applied);
+        self.flips.push(variable);
+        self.apply(variable);
+    }
+
+    /// Reverts the last applied flip and returns its variable, or `None` if there is none.
+    pub fn undo(&mut self) -> Option<usize> {
+        self.applied = self.applied.checked_sub(1)?;
+        for &(index, value) in self.previous_values.pop().unwrap().iter().rev() {
+            self.machine.store(index, value);
+        }
+        Some(self.flips[self.applied])
+    }
+
+    /// Reapplies the last undone flip and returns its variable, or `None` if there is none.
+    pub fn redo(&mut self) -> Option<usize> {
+        let variable = *self.flips.get(self.applied)?;
+        self.apply(variable);
+        Some(variable)
+    }
+
+    /// The variables of the currently applied flips, oldest first.
+    pub fn history(&self) -> &[usize] {
+        &self.flips[..self.applied]
+    }
+
+    pub fn into_inner(self) -> GenericMachine<T> {
+        self.machine
+    }
+
+    fn apply(&mut self, variable: usize) {
+        let GenericMachine {
+            table,
+            values,
+            observables,
+            ..
+        } = &mut self.machine;
+        let variables = table.variables();
+        let cascade = table.cascade(variable);
+        let mut previous = vec![(variable, values[variable])];
+        let value = values[variable].add(T::ONE);
+        store(values, observables, variables, variable, value);
+        for &(term, tail) in &cascade.terms {
+            let delta = values[term];
+            if delta != T::ZERO {
+                previous.push((tail, values[tail]));
+                let value = values[tail].add(delta);
+                store(values, observables, variables, tail, value);
+            }
+        }
+        self.previous_values.push(previous);
+        self.applied += 1;
+    }
+}
+
+impl<T: Value> Deref for RecordedMachine<T> {
+    type Target = GenericMachine<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.machine
+    }
+}
+
+/// Builds a machine from explicitly assigned terms, giving every other term a default value.
+#[derive(Clone, Debug)]
+pub struct MachineBuilder<T: Value> {
+    variables: usize,
+    max_order: usize,
+    default: T,
+    terms: Vec<(Vec<usize>, T)>,
+}
+
+impl<T: Value> MachineBuilder<T> {
+    pub fn new(variables: usize) -> Self {
+        Self {
+            variables,
+            max_order: variables,
+            default: T::ZERO,
+            terms: Vec::new(),
+        }
+    }
+
+    /// Assigns `value` to `term`. Assigning the same term twice makes `build` fail.
+    pub fn term(mut self, term: &[usize], value: T) -> Self {
+        self.terms.push((term.to_vec(), value));
+        self
+    }
+
+    /// Builds a bounded machine, as by `GenericMachine::new_bounded`, with `max_order` clamped the
+    /// same way. Assigning a longer term makes `build` fail.
+    pub fn max_order(mut self, max_order: usize) -> Self {
+        self.max_order = max_order;
+        self
+    }
+
+    /// Sets the value of all terms that are not assigned explicitly (`T::ZERO` unless changed).
+    pub fn default(mut self, value: T) -> Self {
+        self.default = value;
+        self
+    }
+
+    pub fn build(self) -> Result<GenericMachine<T>, BuildError> {
+        let max_order = terms::clamp_max_order(self.variables, self.max_order);
+        let mut values = HashMap::new();
+        for (term, value) in self.terms {
+            if let Some(&variable) = term.iter().find(|&&variable| variable >= self.variables) {
+                return Err(MachineError::VariableOutOfRange {
+                    variable,
+                    variables: self.variables,
+                });
+            }
+            if term.is_empty() || !all_distinct(&term) {
+                return Err(MachineError::MalformedTerm(term));
+            }
+            if values.contains_key(&term) {
+                return Err(MachineError::DuplicateTerm(term));
+            }
+            if term.len() > max_order {
+                return Err(MachineError::TermBeyondMaxOrder { term, max_order });
+            }
+            values.insert(term, value);
+        }
+        Ok(GenericMachine::new_bounded(
+            self.variables,
+            max_order,
+            |term| values.get(term).copied().unwrap_or(self.default),
+        ))
+    }
+}
+
+/// The iterator returned by `GenericMachine::reachable_states`.
+#[derive(Clone, Debug)]
+pub struct ReachableStates<T: Value> {
+    queue: VecDeque<(GenericMachine<T>, Vec<usize>)>,
+    seen: HashSet<GenericMachine<T>>,
+}
+
+impl<T: Value> Iterator for ReachableStates<T> {
+    type Item = (GenericMachine<T>, Vec<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (machine, flips) = self.queue.pop_front()?;
+        for variable in 0..machine.variables() {
+            let mut neighbour = machine.clone();
+            neighbour.flip(variable);
+            if self.seen.insert(neighbour.clone()) {
+                let mut neighbour_flips = flips.clone();
+                neighbour_flips.push(variable);
+                self.queue.push_back((neighbour, neighbour_flips));
+            }
+        }
+        Some((machine, flips))
+    }
+}
+
+/// The iterator returned by `Machine::iter_all`.
+#[derive(Clone, Debug)]
+pub struct AllMachines {
+    table: Arc<TermTable>,
+    next: u128,
+    end: u128,
+}
+
+impl Iterator for AllMachines {
+    type Item = Machine;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let signature = self.next.saturating_add(n as u128);
+        if signature >= self.end {
+            self.next = self.end;
+            return None;
+        }
+        self.next = signature + 1;
+        Some(Machine::from_signature_unchecked(
+            self.table.clone(),
+            signature,
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.next;
+        match usize::try_from(remaining) {
+            Ok(remaining) => (remaining, Some(remaining)),
+            Err(_) => (usize::MAX, None),
+        }
+    }
+}
+
+/// What stays the same across all machines reachable from a given one; see
+/// `GenericMachine::invariants`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invariants<T: Value> {
+    /// The variables whose value never changes, in increasing order.
+    pub constant_variables: Vec<usize>,
+    /// The terms of at least two variables whose value never changes, with that value, in term
+    /// order.
+    pub constant_terms: Vec<(Term, T)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Orbit<T: Value> {
+    /// The distinct machines visited, in order, starting with the initial machine.
+    pub visited: Vec<GenericMachine<T>>,
+    pub outcome: OrbitOutcome,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum OrbitOutcome {
+    /// The last visited machine is left unchanged by another step.
+    FixedPoint,
+    /// Another step from the last visited machine leads back to the machine visited `length`
+    /// steps earlier.
+    Cycle { length: usize },
+    /// No machine repeated within the step limit.
+    Unsettled,
+}
+
+/// How `GenericMachine::flip_cascading` ended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CascadeOutcome {
+    /// No variable was left to trigger after `rounds` rounds.
+    Settled { rounds: usize },
+    /// The machine returned to an earlier state with the same variables left to trigger, so it
+    /// keeps changing forever, repeating every `period` rounds.
+    Oscillation { period: usize },
+    /// The cascade neither settled nor repeated within the round limit.
+    Unsettled,
+}
+
+impl<T: Value> Orbit<T> {
+    /// The number of visited machines before the cycle (or fixed point), or all of them if the
+    /// orbit did not settle.
+    pub fn transient_len(&self) -> usize {
+        self.visited.len() - self.cycle().len()
+    }
+
+    /// The machines on the cycle, in order; a single machine for a fixed point, and none if the
+    /// orbit did not settle.
+    pub fn cycle(&self) -> &[GenericMachine<T>] {
+        let length = match self.outcome {
+            OrbitOutcome::FixedPoint => 1,
+            OrbitOutcome::Cycle { length } => length,
+            OrbitOutcome::Unsettled => 0,
+        };
+        &self.visited[self.visited.len() - length..]
+    }
+
+    fn explore(
+        start: &GenericMachine<T>,
+        max_steps: usize,
+        mut step: impl FnMut(&mut GenericMachine<T>),
+    ) -> Self {
+        let mut visited = vec![start.clone()];
+        let mut indices = HashMap::from([(start.clone(), 0)]);
+        for _ in 0..max_steps {
+            let mut next = visited.last().unwrap().clone();
+            step(&mut next);
+            if let Some(&index) = indices.get(&next) {
+                let outcome = match visited.len() - index {
+                    1 => OrbitOutcome::FixedPoint,
+                    length => OrbitOutcome::Cycle { length },
+                };
+                return Self { visited, outcome };
+            }
+            indices.insert(next.clone(), visited.len());
+            visited.push(next);
+        }
+        Self {
+            visited,
+            outcome: OrbitOutcome::Unsettled,
+        }
+    }
+}
+
+impl Machine {
+    pub fn all(variables: usize) -> Vec<Self> {
+        Self::iter_all(variables).collect()
+    }
+
+    /// Enumerates the machines over `variables` variables in which every term for which `filter`
+    /// returns `Some(value)` has that value, by increasing `signature`. Only the terms for which it
+    /// returns `None` are enumerated, so each fixed term halves the work compared with filtering
+    /// `all`, and machines over more than 4 variables can be enumerated if few terms are free.
+    ///
+    /// Panics if more than 63 terms are free.
+    pub fn all_where(variables: usize, filter: impl Fn(&Term) -> Option<bool>) -> Vec<Self> {
+        let table = TermTable::shared(variables);
+        let fixed = table.terms().iter().map(filter).collect_vec();
+        let free = fixed.iter().positions(Option::is_none).collect_vec();
+        assert!(free.len() < 64, "too many free terms: {}", free.len());
+        (0..1u64 << free.len())
+            .map(|assignment| {
+                let mut values = fixed
+                    .iter()
+                    .map(|value| value.unwrap_or(false))
+                    .collect_vec();
+                for (bit, &index) in free.iter().rev().enumerate() {
+                    values[index] = assignment >> bit & 1 == 1;
+                }
+                Self::from_parts(table.clone(), values, PropagationStrategy::default())
+            })
+            .collect()
+    }
+
+    /// Lazily enumerates all machines over `variables` variables, in the same order as `all`, that
+    /// is, by increasing `signature`.
+    ///
+    /// Panics if `variables > 4`, as there are more than 2^127 machines then.
+    pub fn iter_all(variables: usize) -> AllMachines {
+        let terms = num_terms(variables);
+        assert!(terms < 128, "too many machines over {variables} variables");
+        AllMachines {
+            table: TermTable::shared(variables),
+            next: 0,
+            end: 1 << terms,
+        }
+    }
+
+    /// Creates the machine whose term values, read in term order as a binary number with the first
+    /// term as the most significant bit, are `signature`.
+    pub fn from_signature(variables: usize, signature: u128) -> Result<Self, MachineError> {
+        let table = TermTable::shared(variables);
+        let terms = table.len();
+        if terms < 128 && signature >> terms != 0 {
+            return Err(MachineError::SignatureOutOfRange {
+                signature,
+                variables,
+            });
+        }
+        Ok(Self::from_signature_unchecked(table, signature))
+    }
+
+    fn from_signature_unchecked(table: Arc<TermTable>, signature: u128) -> Self {
+        let terms = table.len();
+        let values = (0..terms)
+            .map(|index| {
+                let bit = terms - 1 - index;
+                bit < 128 && (signature >> bit) & 1 == 1
+            })
+            .collect();
+        Self::from_parts(table, values, PropagationStrategy::default())
+    }
+
+    /// The inverse of `from_signature`, or `None` if a term beyond the last 128 is true.
+    pub fn signature(&self) -> Option<u128> {
+        let mut signature = 0u128;
+        for &value in &self.values {
+            if signature >> 127 != 0 {
+                return None;
+            }
+            signature = signature << 1 | value as u128;
+        }
+        Some(signature)
+    }
+
+    /// A 128-bit hash of the variable count and the true terms that stays the same across runs,
+    /// platforms and versions of this crate, to identify machines outside the process. Like the
+    /// ordering, it ignores the table: a bounded machine has the fingerprint of the full machine
+    /// with the same true terms. The propagation strategy is not hashed.
+    ///
+    /// It is the 128-bit FNV-1a hash of the variable count followed by each true term, as its
+    /// length and then its variables, with the terms ordered by length and lexicographically
+    /// within each length, and all numbers as 8-byte little-endian integers. It depends on neither
+    /// the order nor the layout in which the terms are stored.
+    pub fn fingerprint(&self) -> u128 {
+        const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+        const PRIME: u128 = 0x0000000001000000000000000000013b;
+        let true_terms = self
+            .terms()
+            .filter(|&(_, value)| value)
+            .map(|(term, _)| term)
+            .sorted_by_key(|term| (term.len(), *term))
+            .flat_map(|term| core::iter::once(term.len()).chain(term.iter().copied()));
+        core::iter::once(self.variables())
+            .chain(true_terms)
+            .map(|number| number as u64)
+            .flat_map(u64::to_le_bytes)
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u128).wrapping_mul(PRIME)
+            })
+    }
+
+    /// Returns one machine per class of machines that are equal up to relabeling of variables,
+    /// namely the one that `canonicalize` maps the class to.
+    pub fn all_canonical(variables: usize) -> Vec<Self> {
+        let table = TermTable::shared(variables);
+        let relabelings = (0..variables)
+            .permutations(variables)
+            .map(|relabeling| relabeling_sources(&table, &relabeling))
+            .collect_vec();
+        (0..num_terms(variables))
+            .map(|_| [false, true].into_iter())
+            .multi_cartesian_product()
+            .filter(|values| {
+                // canonical means no relabeling produces lexicographically smaller values
+                relabelings.iter().all(|sources| {
+                    sources
+                        .iter()
+                        .map(|&source| values[source])
+                        .cmp(values.iter().copied())
+                        != Ordering::Less
+                })
+            })
+            .map(|values| Self::from_parts(table.clone(), values, PropagationStrategy::default()))
+            .collect()
+    }
+
+    /// Sets several variables at once: the variables whose value differs from their target are
+    /// flipped together as by `flip_many`, except that the assigned variables themselves end
+    /// up with their target values even if another flip's cascade would have changed them.
+    ///
+    /// Panics if a variable is out of range or assigned more than once.
+    pub fn set_many(&mut self, assignments: &[(usize, bool)]) {
+        assert!(
+            all_distinct(assignments.iter().map(|&(variable, _)| variable)),
+            "a variable is assigned more than once"
+        );
+        let to_flip = assignments
+            .iter()
+            .filter(|&&(variable, value)| self.get(variable) != value)
+            .map(|&(variable, _)| variable)
+            .collect_vec();
+        self.flip_many(&to_flip);
+        for &(variable, value) in assignments {
+            let index = self.table.variable_index(variable);
+            self.store(index, value);
+        }
+    }
+
+    /// The values of all variables at once; bit `v` is the value of variable `v`. The mask is kept
+    /// up to date by every change of the machine, so this takes constant time.
+    ///
+    /// Panics if the machine has more than 64 variables.
+    pub fn observables(&self) -> u64 {
+        assert!(self.variables() <= 64, "more than 64 variables");
+        self.observables
+    }
+
+    /// Sets every variable `v` to bit `v` of `mask`, as by `set_many`, so that afterwards
+    /// `self.observables() == mask`.
+    ///
+    /// Panics if `mask` has bits set for variables the machine does not have.
+    pub fn set_observables(&mut self, mask: u64) {
+        assert!(
+            self.variables() >= 64 || mask >> self.variables() == 0,
+            "mask {mask:#b} has bits beyond the machine's {} variables",
+            self.variables()
+        );
+        let assignments = (0..self.variables())
+            .map(|variable| (variable, mask >> variable & 1 == 1))
+            .collect_vec();
+        self.set_many(&assignments);
+    }
+
+    /// Gives the variables the names in `names`, in order, for use in place of their indices.
+    pub fn with_names(self, names: &[&str]) -> Result<NamedMachine, MachineError> {
+        NamedMachine::new(self, names)
+    }
+
+    /// A layout of the terms grouped by order and aligned in columns, which is easier to read than
+    /// `Debug` or `Display` output for more than a few variables; see `pretty::Pretty`.
+    pub fn pretty(&self) -> pretty::Pretty<'_> {
+        pretty::Pretty::new(self)
+    }
+
+    /// Wraps this machine so that its flips and sets are recorded in a `trace::Trace`.
+    ///
+    /// The machine must have at most 64 variables, as the trace records their values as bit masks.
+    #[cfg(feature = "std")]
+    pub fn into_traced(self) -> trace::TracedMachine {
+        trace::TracedMachine {
+            machine: self,
+            trace: trace::Trace::default(),
+        }
+    }
+
+    /// Takes a bit-packed copy of the current values.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            packed: PackedMachine::from(self),
+        }
+    }
+
+    /// Puts back the values from `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), MachineError> {
+        self.check_snapshot(snapshot)?;
+        for (index, value) in self.values.iter_mut().enumerate() {
+            *value = snapshot.packed.bit(index);
+        }
+        self.sync_observables();
+        Ok(())
+    }
+
+    /// Lists the terms whose value changed since `snapshot` was taken, in term order.
+    pub fn changes_since(&self, snapshot: &Snapshot) -> Result<Vec<Term>, MachineError> {
+        self.check_snapshot(snapshot)?;
+        Ok(self
+            .table
+            .terms()
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .filter(|&(index, (_, &value))| snapshot.packed.bit(index) != value)
+            .map(|(_, (term, _))| term.clone())
+            .collect())
+    }
+
+    fn check_snapshot(&self, snapshot: &Snapshot) -> Result<(), MachineError> {
+        self.check_table(snapshot.packed.table())
+    }
+
+    /// The machine whose terms are the XOR (the sum in GF(2)) of the corresponding terms of `self`
+    /// and `other`.
+    pub fn xor(&self, other: &Self) -> Result<Self, MachineError> {
+        self.combine_terms(other, |left, right| left ^ right)
+    }
+
+    /// The machine whose terms are true where the corresponding terms of both `self` and `other`
+    /// are.
+    pub fn and(&self, other: &Self) -> Result<Self, MachineError> {
+        self.combine_terms(other, |left, right| left & right)
+    }
+
+    /// The machine whose terms are true where the corresponding term of `self` or `other` is.
+    pub fn or(&self, other: &Self) -> Result<Self, MachineError> {
+        self.combine_terms(other, |left, right| left | right)
+    }
+
+    /// The machine with every term value negated.
+    pub fn negate_terms(&self) -> Self {
+        Self::from_parts(
+            self.table.clone(),
+            self.values.iter().map(|&value| !value).collect(),
+            self.strategy,
+        )
+    }
+
+    fn combine_terms(
+        &self,
+        other: &Self,
+        combine: impl Fn(bool, bool) -> bool,
+    ) -> Result<Self, MachineError> {
+        self.check_table(other.table())?;
+        Ok(Self::from_parts(
+            self.table.clone(),
+            self.values
+                .iter()
+                .zip(&other.values)
+                .map(|(&left, &right)| combine(left, right))
+                .collect(),
+            self.strategy,
+        ))
+    }
+
+    /// Tabulates the value of `output` after driving the `inputs` into each of their assignments.
+    ///
+    /// Every row starts from a fresh clone of `self`, on which the inputs are `set` one after the
+    /// other in the order they are given in `inputs`. Because setting one variable may change
+    /// others, this order matters. Rows are ordered like binary numbers, with the first input being
+    /// the most significant.
+    pub fn truth_table(&self, output: usize, inputs: &[usize]) -> Vec<(Vec<bool>, bool)> {
+        inputs
+            .iter()
+            .map(|_| [false, true].into_iter())
+            .multi_cartesian_product()
+            .map(|assignment| {
+                let mut machine = self.clone();
+                for (&input, &value) in inputs.iter().zip(assignment.iter()) {
+                    machine.set(input, value);
+                }
+                let value = machine.get(output);
+                (assignment, value)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn random(variables: usize, rng: &mut impl rand::Rng) -> Self {
+        Self::new(variables, |_| rng.random())
+    }
+
+    /// Creates a machine in which each term is independently true with probability `density`.
+    ///
+    /// Panics if `density` is not in `0.0..=1.0`.
+    #[cfg(feature = "rand")]
+    pub fn random_with_density(variables: usize, density: f64, rng: &mut impl rand::Rng) -> Self {
+        Self::new(variables, |_| rng.random_bool(density))
+    }
+
+    #[cfg(feature = "rand")]
+    pub fn random_from_seed(variables: usize, seed: u64) -> Self {
+        use rand::SeedableRng;
+        Self::random(variables, &mut rand::rngs::StdRng::seed_from_u64(seed))
+    }
+
+    /// Finds a machine that behaves as in the given examples; see `synthesis::synthesize`.
+    pub fn synthesize(
+        variables: usize,
+        examples: &[(simulate::Script<bool>, u64)],
+    ) -> Result<Self, synthesis::SynthesisError> {
+        synthesis::synthesize(variables, examples)
+    }
+
+    /// Partitions all machines over `variables` variables into the connected components of the
+    /// relation "one machine becomes the other by a single `flip`".
+    pub fn flip_equivalence_classes(variables: usize) -> Vec<Vec<Self>> {
+        let classes = classify(variables);
+        let mut members = vec![Vec::new(); classes.len()];
+        for machine in Self::iter_all(variables) {
+            members[classes.class_of(&machine).unwrap()].push(machine);
+        }
+        members
+    }
+}
+
+/// Machines are ordered canonically: by variable count, then lexicographically by their term values
+/// in term order, with the terms missing from a bounded table counting as zero, then by the maximal
+/// order of their tables, and last by propagation strategy. So a bounded machine sorts next to the
+/// full machine with the same terms, and machines over the same table are in the order of
+/// `Machine::signature`.
+impl<T: Value> Ord for GenericMachine<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn padded<T: Value>(values: &[T], len: usize) -> impl Iterator<Item = T> + '_ {
+            (values.iter().copied())
+                .chain(core::iter::repeat(T::ZERO))
+                .take(len)
+        }
+        let len = self.values.len().max(other.values.len());
+        self.variables()
+            .cmp(&other.variables())
+            .then_with(|| padded(&self.values, len).cmp(padded(&other.values, len)))
+            .then_with(|| self.table.cmp(&other.table))
+            .then_with(|| self.strategy.cmp(&other.strategy))
+    }
+}
+
+impl<T: Value> PartialOrd for GenericMachine<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Value> Debug for GenericMachine<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut debug_map = f.debug_map();
+        for (term, value) in self.terms() {
+            debug_map.entry(term, &value);
+        }
+        debug_map.finish()
+    }
+}
+
+// like `Itertools::all_unique`, which needs `std`
+pub(crate) fn all_distinct<T: Ord>(items: impl IntoIterator<Item = T>) -> bool {
+    let mut seen = BTreeSet::new();
+    items.into_iter().all(|item| seen.insert(item))
+}
+
+fn factorial(n: usize) -> usize {
+    (1..=n).product()
+}
+
+fn permutations(n: usize, k: usize) -> usize {
+    factorial(n) / factorial(n - k)
+}
+
+fn num_terms(variables: usize) -> usize {
+    (1..=variables).map(|k| permutations(variables, k)).sum()
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use crate::{
+        CascadeOutcome, GenericMachine, Machine, MachineBuilder, MachineError, OrbitOutcome,
+        PropagationStrategy, Term, TermChange, TermTable, Value,
+    };
+    use itertools::Itertools;
+    use std::collections::{BTreeSet, HashSet};
+    use std::sync::Arc;
+
+    #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    struct Gf3(u8);
+
+    impl Value for Gf3 {
+        const ZERO: Self = Gf3(0);
+        const ONE: Self = Gf3(1);
+
+        fn add(self, other: Self) -> Self {
+            Gf3((self.0 + other.0) % 3)
+        }
+    }
+
+    #[test]
+    fn print_terms() {
+        Machine::new(5, |term| {
+            println!("{term:?}");
+            true
+        });
+    }
+
+    #[test]
+    fn three() {
+        let mut system = Machine::new(3, |term| match term {
+            [0] => false,
+            [1] => true,
+            [2] => true,
+            [0, 1] => true,
+            [0, 2] => true,
+            [1, 0] => true,
+            [1, 2] => true,
+            [2, 0] => true,
+            [2, 1] => false,
+            [0, 1, 2] => false,
+            [0, 2, 1] => true,
+            [1, 0, 2] => false,
+            [1, 2, 0] => false,
+            [2, 0, 1] => false,
+            [2, 1, 0] => false,
+            _ => panic!(),
+        });
+        println!("{:?}", system);
+        system.flip(0);
+        println!("{:?}", system);
+    }
+
+    #[test]
+    fn experimental() {
+        let mut system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => false,
+            _ => panic!(),
+        });
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        system.set(0, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), true);
+        system.set(0, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        system.set(1, true);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+        system.set(1, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        system.set(1, true);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+        system.set(0, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), false);
+        system.set(1, false);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), false);
+        system.set(0, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+    }
+
+    #[test]
+    fn equals() {
+        let mut system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => true,
+            _ => panic!(),
+        });
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        system.set(0, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), true);
+        system.set(0, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        system.set(1, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), true);
+        system.set(1, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+    }
+
+    #[test]
+    fn not_equals() {
+        let mut system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => true,
+            [0, 1] => true,
+            [1, 0] => true,
+            _ => panic!(),
+        });
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+        system.set(0, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), false);
+        system.set(0, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+        system.set(1, true);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+        system.set(1, false);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), false);
+    }
+
+    #[test]
+    fn flip_impact() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => true,
+            _ => panic!(),
+        });
+        assert!(system.flip_impact(0).contains(&1));
+        assert_eq!(system.flip_impact(0), BTreeSet::from([0, 1]));
+
+        let mut flipped = system.clone();
+        flipped.flip(0);
+        assert_eq!(system.flip_impact(1), flipped.flip_impact(1));
+    }
+
+    #[test]
+    fn canonicalize() {
+        let system = Machine::new(3, |term| matches!(term, [0] | [0, 1] | [1, 2] | [2, 0, 1]));
+        let swapped = Machine::new(3, |term| matches!(term, [1] | [1, 0] | [0, 2] | [2, 1, 0]));
+        assert_ne!(system, swapped);
+        assert_eq!(system.canonicalize(), swapped.canonicalize());
+        assert!(system.is_isomorphic(&swapped));
+        assert!(!system.is_isomorphic(&Machine::new(3, |_| false)));
+    }
+
+    #[test]
+    fn flip_equivalence_classes() {
+        for (variables, count) in [(1, 1), (2, 5), (3, 2024)] {
+            let classes = Machine::flip_equivalence_classes(variables);
+            assert_eq!(classes.len(), count);
+            let total = classes.iter().map(Vec::len).sum::<usize>();
+            assert_eq!(total, Machine::all(variables).len());
+            for class in &classes {
+                for machine in class {
+                    for variable in 0..variables {
+                        let mut neighbour = machine.clone();
+                        neighbour.flip(variable);
+                        assert!(class.contains(&neighbour));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gf3() {
+        let mut system = GenericMachine::new(2, |term| match term {
+            [0, 1] => Gf3(2),
+            _ => Gf3(0),
+        });
+        system.flip(0);
+        assert_eq!(system.get(0), Gf3(1));
+        assert_eq!(system.get(1), Gf3(2));
+        system.flip(0);
+        assert_eq!(system.get(0), Gf3(2));
+        assert_eq!(system.get(1), Gf3(1));
+        system.flip(0);
+        assert_eq!(system.get(0), Gf3(0));
+        assert_eq!(system.get(1), Gf3(0));
+        system.set(1, Gf3(2));
+        assert_eq!(system.get(0), Gf3(0));
+        assert_eq!(system.get(1), Gf3(2));
+        system.set(0, Gf3(1));
+        assert_eq!(system.get(1), Gf3(1));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn random() {
+        use rand::SeedableRng;
+
+        assert_eq!(
+            Machine::random_from_seed(3, 42),
+            Machine::random_from_seed(3, 42)
+        );
+        let distinct = (0..16)
+            .map(|seed| Machine::random_from_seed(3, seed))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(distinct.len() > 1);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let empty = Machine::random_with_density(3, 0.0, &mut rng);
+        assert!(empty.values().iter().all(|&value| !value));
+        let full = Machine::random_with_density(3, 1.0, &mut rng);
+        assert!(full.values().iter().all(|&value| value));
+        let sparse = Machine::random_with_density(4, 0.1, &mut rng);
+        assert!(sparse.values().iter().filter(|&&value| value).count() < 16);
+    }
+
+    #[test]
+    fn diff() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => false,
+            _ => panic!(),
+        });
+        let mut flipped = system.clone();
+        flipped.flip(0);
+        assert_eq!(
+            system.diff(&flipped).unwrap().changes(),
+            [
+                TermChange {
+                    term: &[0][..],
+                    old: false,
+                    new: true
+                },
+                TermChange {
+                    term: &[1][..],
+                    old: false,
+                    new: true
+                }
+            ]
+        );
+        assert!(system.diff(&system).unwrap().is_empty());
+        assert_eq!(
+            system.diff(&Machine::new(3, |_| false)),
+            Err(MachineError::VariablesMismatch { left: 2, right: 3 })
+        );
+    }
+
+    #[test]
+    fn reset_to_initial() {
+        let producer = |term: &[usize]| matches!(term, [1] | [0, 1] | [2, 0] | [1, 2, 0]);
+        let mut system = Machine::new_resettable(3, producer);
+        system.flip(0);
+        system.set(2, true);
+        system.flip(1);
+        assert_ne!(*system, Machine::new(3, producer));
+        system.reset_to_initial();
+        assert_eq!(*system, Machine::new(3, producer));
+        assert_eq!(system.into_inner(), Machine::new(3, producer));
+    }
+
+    #[test]
+    fn reachable() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => false,
+            _ => panic!(),
+        });
+        let reachable = system.reachable();
+        assert_eq!(reachable.len(), 4);
+        assert_eq!(reachable[0], system);
+        let class = Machine::flip_equivalence_classes(2)
+            .into_iter()
+            .find(|class| class.contains(&system))
+            .unwrap();
+        assert_eq!(reachable.len(), class.len());
+        assert!(reachable.iter().all(|machine| class.contains(machine)));
+    }
+
+    #[test]
+    fn truth_table() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => true,
+            _ => panic!(),
+        });
+        assert_eq!(
+            system.truth_table(1, &[0]),
+            vec![(vec![false], false), (vec![true], true)]
+        );
+        assert_eq!(
+            system.truth_table(0, &[1]),
+            vec![(vec![false], false), (vec![true], true)]
+        );
+        assert_eq!(system.truth_table(0, &[]), vec![(vec![], false)]);
+    }
+
+    #[test]
+    fn ord() {
+        let mut machines = (0..3)
+            .flat_map(|variables| Machine::all(variables).into_iter().rev())
+            .collect_vec();
+        let set = machines.iter().cloned().collect::<BTreeSet<_>>();
+        machines.sort();
+        assert_eq!(machines, set.into_iter().collect_vec());
+        assert!(machines.is_sorted_by_key(|machine| machine.variables()));
+        assert!(Machine::new(1, |_| false) < Machine::new(1, |_| true));
+        assert!(Machine::new(1, |_| true) < Machine::new(2, |_| false));
+
+        let producer = |term: &[usize]| term == [0, 1];
+        let bounded = Machine::new_bounded(3, 2, producer);
+        let full = Machine::new(3, producer);
+        assert!(bounded < full);
+        assert!(Machine::new_bounded(3, 2, |term| term == [0, 2]) < full);
+        assert!(Machine::new(3, |term| term == [0, 1, 2]) < bounded);
+        let descending = full.clone().with_strategy(PropagationStrategy::Descending);
+        assert!(full < descending);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let producer = |term: &[usize]| matches!(term, [1] | [0, 2] | [2, 1, 0]);
+        let machine = Machine::new(3, producer);
+        // FNV-1a over 3, then [1], [0, 2] and [2, 1, 0] each as its length and variables
+        assert_eq!(
+            machine.fingerprint(),
+            86056625610778776718931184031619417198
+        );
+        assert_eq!(
+            Machine::new(0, |_| false).fingerprint(),
+            208941959702825956589139152005585860589
+        );
+        let mut truncated = machine.clone();
+        truncated.truncate(2);
+        assert_eq!(
+            truncated.fingerprint(),
+            Machine::new_bounded(3, 2, producer).fingerprint()
+        );
+        assert_ne!(truncated.fingerprint(), machine.fingerprint());
+        let strategy = machine
+            .clone()
+            .with_strategy(PropagationStrategy::Ascending);
+        assert_eq!(strategy.fingerprint(), machine.fingerprint());
+        let fingerprints = Machine::all(2)
+            .iter()
+            .map(Machine::fingerprint)
+            .collect_vec();
+        assert!(fingerprints.iter().all_unique());
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Machine::new(3, |_| true).validate(), Ok(()));
+        let broken = Machine::from_parts(
+            TermTable::shared(2),
+            vec![false; 3],
+            PropagationStrategy::default(),
+        );
+        assert_eq!(
+            broken.validate(),
+            Err(MachineError::WrongValueCount {
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn flip_traced() {
+        let system = Machine::new(3, |term| {
+            matches!(
+                term,
+                [1] | [2] | [0, 1] | [0, 2] | [1, 0] | [1, 2] | [2, 0] | [0, 2, 1]
+            )
+        });
+        let mut traced = system.clone();
+        let trace = traced.flip_traced(0);
+        assert_eq!(trace, vec![vec![0], vec![1], vec![2], vec![2, 1]]);
+
+        let mut flipped = system.clone();
+        flipped.flip(0);
+        assert_eq!(traced, flipped);
+        let changed = system
+            .diff(&flipped)
+            .unwrap()
+            .into_iter()
+            .map(|change| change.term.to_vec())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(changed, trace.into_iter().collect());
+    }
+
+    #[test]
+    fn disjoint_union() {
+        let equals = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        let not_equals = Machine::new(2, |term| matches!(term, [1] | [0, 1] | [1, 0]));
+        let mut system = equals.disjoint_union(&not_equals);
+        assert_eq!(system.variables(), 4);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        assert_eq!(system.get(2), false);
+        assert_eq!(system.get(3), true);
+        system.set(0, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), true);
+        assert_eq!(system.get(2), false);
+        assert_eq!(system.get(3), true);
+        system.set(2, true);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), true);
+        assert_eq!(system.get(2), true);
+        assert_eq!(system.get(3), false);
+        system.set(1, false);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), false);
+        assert_eq!(system.get(2), true);
+        assert_eq!(system.get(3), false);
+    }
+
+    #[test]
+    fn orbit() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => false,
+            _ => panic!(),
+        });
+        let orbit = system.orbit(0, true, 10);
+        assert_eq!(orbit.outcome, OrbitOutcome::FixedPoint);
+        assert_eq!(orbit.visited.len(), 2);
+        assert_eq!(orbit.visited[0], system);
+        assert_eq!(orbit.visited[1].get(0), true);
+        assert_eq!(orbit.visited[1].get(1), true);
+
+        let orbit = system.orbit(0, false, 10);
+        assert_eq!(orbit.outcome, OrbitOutcome::FixedPoint);
+        assert_eq!(orbit.visited, vec![system.clone()]);
+
+        let orbit = system.orbit(0, true, 0);
+        assert_eq!(orbit.outcome, OrbitOutcome::Unsettled);
+        assert_eq!(orbit.visited, vec![system]);
+    }
+
+    #[test]
+    fn builder() {
+        let built = Machine::builder(2)
+            .term(&[0, 1], true)
+            .term(&[1, 0], true)
+            .build()
+            .unwrap();
+        let equals = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => true,
+            _ => panic!(),
+        });
+        assert_eq!(built, equals);
+        assert_eq!(
+            Machine::builder(2).default(true).term(&[0], false).build(),
+            Ok(Machine::new(2, |term| term != [0]))
+        );
+        assert_eq!(
+            Machine::builder(2).term(&[0, 2], true).build(),
+            Err(MachineError::VariableOutOfRange {
+                variable: 2,
+                variables: 2
+            })
+        );
+        assert_eq!(
+            Machine::builder(2).term(&[1, 1], true).build(),
+            Err(MachineError::MalformedTerm(vec![1, 1]))
+        );
+        assert_eq!(
+            Machine::builder(2).term(&[], true).build(),
+            Err(MachineError::MalformedTerm(vec![]))
+        );
+        assert_eq!(
+            MachineBuilder::new(2)
+                .term(&[0, 1], true)
+                .term(&[0, 1], false)
+                .build(),
+            Err(MachineError::DuplicateTerm(vec![0, 1]))
+        );
+        assert_eq!(
+            MachineBuilder::new(3).term(&[2, 0], true).build(),
+            Ok(Machine::new(3, |term| term == [2, 0]))
+        );
+    }
+
+    #[test]
+    fn flip_is_involution() {
+        let counterexamples = Machine::all(2)
+            .into_iter()
+            .flat_map(|machine| (0..2).map(move |variable| (machine.clone(), variable)))
+            .filter(|(machine, variable)| !machine.flip_is_involution(*variable))
+            .collect_vec();
+        assert_eq!(counterexamples, vec![]);
+    }
+
+    #[test]
+    fn from_values() {
+        let system = Machine::new(3, |term| matches!(term, [2] | [0, 1] | [1, 2, 0]));
+        assert_eq!(
+            Machine::from_values(system.variables(), system.values().to_vec()),
+            Ok(system)
+        );
+        assert_eq!(
+            Machine::from_values(2, vec![true; 5]),
+            Err(MachineError::WrongValueCount {
+                expected: 4,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let system = Machine::new(3, |_| false);
+        assert_eq!(system.variables(), 3);
+        assert_eq!(system.values().len(), crate::num_terms(3));
+    }
+
+    #[test]
+    fn cascade_flip() {
+        fn scanning_flip(machine: &mut Machine, variable: usize) {
+            let table = machine.table.clone();
+            let index = table.index(&[variable]).unwrap();
+            machine.store(index, !machine.values[index]);
+            let terms_to_flip = machine
+                .values
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|&(index, value)| table.term(index)[0] == variable && value)
+                .map(|(index, _)| table.term(index)[1..].to_vec())
+                .filter(|term| !term.is_empty())
+                .collect_vec();
+            for term in terms_to_flip {
+                let index = table.index(&term).unwrap();
+                machine.store(index, !machine.values[index]);
+            }
+        }
+
+        for machine in Machine::all(3) {
+            for variable in 0..3 {
+                let mut expected = machine.clone();
+                scanning_flip(&mut expected, variable);
+                let mut actual = machine.clone();
+                actual.flip(variable);
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn all_where() {
+        let first_order_false = |term: &Term| (term.len() == 1).then_some(false);
+        let machines = Machine::all_where(3, first_order_false);
+        assert_eq!(machines.len(), 1 << 12);
+        assert!(machines.is_sorted());
+        let filtered = Machine::iter_all(3)
+            .filter(|machine| (0..3).all(|variable| !machine.get(variable)))
+            .collect_vec();
+        assert_eq!(machines, filtered);
+
+        // 5 variables have 325 terms, of which only the 4 pairs starting with 0 are free
+        let pairs = Machine::all_where(5, |term| match term[..] {
+            [0, _] => None,
+            _ => Some(term[..] == [1, 2, 3]),
+        });
+        assert_eq!(pairs.len(), 16);
+        assert!(pairs.iter().all_unique());
+        assert!(
+            pairs
+                .iter()
+                .all(|machine| machine.get_term(&Term::new([1, 2, 3])))
+        );
+        assert_eq!(
+            Machine::all_where(2, |_| Some(true)),
+            [Machine::new(2, |_| true)]
+        );
+    }
+
+    #[test]
+    fn all_canonical() {
+        let representatives = Machine::all_canonical(3);
+        let set = representatives.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(set.len(), representatives.len());
+        for representative in &representatives {
+            assert_eq!(&representative.canonicalize(), representative);
+        }
+        for machine in Machine::all(3) {
+            assert!(set.contains(&machine.canonicalize()));
+        }
+        assert_eq!(Machine::all_canonical(2).len(), 10);
+    }
+
+    #[test]
+    fn terms() {
+        let mut system = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        assert_eq!(system.get_term(&Term::new([0, 1])), true);
+        assert_eq!(system.get_term(&Term::new([1])), false);
+        system.set_term(&Term::new([1, 0]), false);
+        system.flip_term(&Term::new([1]));
+        assert_eq!(system.get(1), true);
+        assert_eq!(
+            system.terms().collect_vec(),
+            vec![
+                (&Term::new([0]), false),
+                (&Term::new([1]), true),
+                (&Term::new([0, 1]), true),
+                (&Term::new([1, 0]), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_all() {
+        assert_eq!(Machine::iter_all(3).collect_vec(), Machine::all(3));
+        assert_eq!(Machine::iter_all(2).count(), 16);
+        for (signature, machine) in Machine::iter_all(2).enumerate() {
+            assert_eq!(machine.signature(), Some(signature as u128));
+            assert_eq!(Machine::from_signature(2, signature as u128), Ok(machine));
+        }
+
+        let mut machines = Machine::iter_all(4);
+        assert_eq!(machines.size_hint(), (usize::MAX, None));
+        let first = machines.next().unwrap();
+        assert!(first.values().iter().all(|&value| !value));
+        let third = machines.nth(1).unwrap();
+        assert_eq!(third.signature(), Some(2));
+        assert_eq!(third.get(0), false);
+        assert!(third.values()[62]);
+        let skipped = Machine::iter_all(4).nth(1 << (64 - 1 - 3)).unwrap();
+        assert_eq!(skipped.get(3), true);
+        assert_eq!(skipped.values().iter().filter(|&&value| value).count(), 1);
+
+        assert_eq!(
+            Machine::from_signature(2, 16),
+            Err(MachineError::SignatureOutOfRange {
+                signature: 16,
+                variables: 2
+            })
+        );
+    }
+
+    #[test]
+    fn reachable_states() {
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2] | [2, 0, 1]));
+        let states = system.reachable_states().collect_vec();
+        assert_eq!(states[0], (system.clone(), vec![]));
+        assert_eq!(
+            states
+                .iter()
+                .map(|(machine, _)| machine.clone())
+                .collect_vec(),
+            system.reachable()
+        );
+        for (machine, flips) in &states {
+            let mut replayed = system.clone();
+            for &variable in flips {
+                replayed.flip(variable);
+            }
+            assert_eq!(&replayed, machine);
+        }
+        assert!(states.is_sorted_by_key(|(_, flips)| flips.len()));
+        assert_eq!(system.reachable_states().nth(1).unwrap().1, vec![0]);
+    }
+
+    #[test]
+    fn sequence_orbit() {
+        let system = Machine::new(2, |term| match term {
+            [0] => false,
+            [1] => false,
+            [0, 1] => true,
+            [1, 0] => false,
+            _ => panic!(),
+        });
+        let orbit = system.sequence_orbit(&[0, 1]);
+        assert_eq!(orbit.outcome, OrbitOutcome::Cycle { length: 2 });
+        assert_eq!(orbit.transient_len(), 0);
+        assert_eq!(orbit.cycle().len(), 2);
+        assert_eq!(orbit.visited[0], system);
+        assert_eq!(orbit.visited[1].get(0), true);
+        assert_eq!(orbit.visited[1].get(1), false);
+        let mut after = orbit.visited[1].clone();
+        after.flip(0);
+        after.flip(1);
+        assert_eq!(after, system);
+
+        let orbit = system.sequence_orbit(&[0, 0]);
+        assert_eq!(orbit.outcome, OrbitOutcome::FixedPoint);
+        assert_eq!(orbit.cycle(), std::slice::from_ref(&system));
+
+        for machine in Machine::all(2) {
+            let orbit = machine.sequence_orbit(&[1, 0, 0, 1, 0]);
+            assert_eq!(orbit.transient_len(), 0);
+            let distinct = orbit.visited.iter().collect::<HashSet<_>>();
+            assert_eq!(distinct.len(), orbit.visited.len());
+        }
+    }
+
+    #[test]
+    fn permute_variables() {
+        let system = Machine::new(3, |term| matches!(term, [0] | [0, 1] | [1, 2] | [2, 0, 1]));
+        let permuted = system.permute_variables(&[1, 2, 0]);
+        assert_eq!(
+            permuted,
+            Machine::new(3, |term| matches!(term, [1] | [1, 2] | [2, 0] | [0, 1, 2]))
+        );
+        assert_eq!(system.permute_variables(&[0, 1, 2]), system);
+        assert_eq!(system.is_isomorphic_to(&permuted), Some(vec![1, 2, 0]));
+        assert_eq!(
+            permuted.is_isomorphic_to(&system).map(|relabeling| system
+                .permute_variables(&[1, 2, 0])
+                .permute_variables(&relabeling)),
+            Some(system.clone())
+        );
+        assert_eq!(system.is_isomorphic_to(&Machine::new(3, |_| true)), None);
+    }
+
+    #[test]
+    fn restrict() {
+        let equals = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        let not_equals = Machine::new(2, |term| matches!(term, [1] | [0, 1] | [1, 0]));
+        let union = equals.disjoint_union(&not_equals);
+        assert_eq!(union.restrict(&[0, 1]), equals);
+        assert_eq!(union.restrict(&[2, 3]), not_equals);
+        assert_eq!(
+            union.restrict(&[3, 2]),
+            not_equals.permute_variables(&[1, 0])
+        );
+        assert_eq!(union.restrict(&[]), Machine::new(0, |_| false));
+
+        let system = Machine::new(3, |term| matches!(term, [2] | [0, 2] | [0, 1, 2]));
+        assert_eq!(
+            system.restrict(&[0, 2]),
+            Machine::new(2, |term| matches!(term, [1] | [0, 1]))
+        );
+    }
+
+    #[test]
+    fn compose() {
+        let copy = Machine::new(1, |_| false);
+        assert_eq!(copy.compose(&copy), copy.disjoint_union(&copy));
+
+        // wire variable 0 of the first machine to variable 0 of the second one
+        let mut wired = copy.compose_with(&copy, |term| term == [0, 1]);
+        assert_eq!(wired.restrict(&[0]), copy);
+        assert_eq!(wired.restrict(&[1]), copy);
+        wired.set(0, true);
+        assert_eq!(wired.get(1), true);
+        wired.set(1, false);
+        assert_eq!(wired.get(0), true);
+    }
+
+    #[test]
+    fn checked_access() {
+        let mut system = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        let out_of_range = Err(MachineError::VariableOutOfRange {
+            variable: 2,
+            variables: 2,
         });
-        consumer(cache)
-    })
-}
+        assert_eq!(system.try_get(2), out_of_range.clone().map(|()| false));
+        assert_eq!(system.try_flip(2), out_of_range.clone());
+        assert_eq!(system.try_set(2, true), out_of_range);
+        assert_eq!(system.try_set(0, true), Ok(()));
+        assert_eq!(system.try_get(1), Ok(true));
+        assert_eq!(system.try_flip(1), Ok(()));
+        assert_eq!(system.try_get(0), Ok(false));
+    }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-pub struct Machine {
-    variables: usize,
-    values: Vec<bool>,
-}
+    #[test]
+    fn flip_many() {
+        let system = Machine::new(3, |term| term == [0, 1, 2]);
+        let mut simultaneous = system.clone();
+        simultaneous.flip_many(&[0, 1]);
+        let mut sequential = system.clone();
+        sequential.flip(0);
+        sequential.flip(1);
+        // flipping 0 sets [1, 2], which the sequential flip of 1 then sees
+        assert_eq!(sequential.get(2), true);
+        assert_eq!(simultaneous.get(2), false);
+        assert_eq!(simultaneous.get_term(&Term::new([1, 2])), true);
 
-impl Machine {
-    pub fn new(
-        variables: usize,
-        mut initial_values_producer: impl FnMut(&[usize]) -> bool,
-    ) -> Self {
-        let mut values = Vec::new();
-        index_to_term(variables, |index_to_term| {
-            for term in index_to_term {
-                let value = initial_values_producer(term.as_slice());
-                values.push(value);
+        for machine in Machine::all(2) {
+            for variable in 0..2 {
+                let mut single = machine.clone();
+                single.flip_many(&[variable]);
+                let mut flipped = machine.clone();
+                flipped.flip(variable);
+                assert_eq!(single, flipped);
+            }
+            let mut twice = machine.clone();
+            twice.flip_many(&[1, 1]);
+            assert_eq!(twice, machine);
+        }
+    }
+
+    #[test]
+    fn propagation_strategies() {
+        let strategies = [
+            PropagationStrategy::Simultaneous,
+            PropagationStrategy::Ascending,
+            PropagationStrategy::Descending,
+        ];
+        for machine in Machine::all(3) {
+            for variable in 0..3 {
+                let traces = strategies.map(|strategy| {
+                    let mut machine = machine.clone().with_strategy(strategy);
+                    let trace = machine.flip_traced(variable);
+                    (machine.values().to_vec(), trace)
+                });
+                assert!(traces.iter().all(|(values, _)| *values == traces[0].0));
+                assert_eq!(traces[0].1, traces[1].1);
+                assert_eq!(
+                    traces[1].1.iter().rev().collect_vec(),
+                    traces[2].1.iter().collect_vec()
+                );
             }
+        }
+
+        let system = Machine::new(3, |term| term == [0, 1, 2]);
+        let observables = strategies.map(|strategy| {
+            let mut machine = system.clone().with_strategy(strategy);
+            machine.flip_many(&[1, 0]);
+            machine.observables()
         });
-        Self { variables, values }
+        // only flipping 0 before 1 lets the flip of 1 see the [1, 2] set by the flip of 0
+        assert_eq!(observables, [0b011, 0b111, 0b011]);
+
+        let mut machine = system.with_strategy(PropagationStrategy::Ascending);
+        assert_ne!(machine, Machine::new(3, |term| term == [0, 1, 2]));
+        machine.set_strategy(PropagationStrategy::default());
+        assert_eq!(machine.strategy(), PropagationStrategy::Simultaneous);
     }
 
-    pub fn all(variables: usize) -> Vec<Self> {
-        let mut machines = Vec::new();
-        for signature in (0..(1..=variables).map(|k| permutations(variables, k)).sum())
-            .map(|_| [false, true].into_iter())
-            .multi_cartesian_product()
-        {
-            let mut term_index = 0;
-            machines.push(Self::new(variables, |term| {
-                let result = signature[term_index];
-                term_index += 1;
-                result
-            }));
+    #[test]
+    fn flip_cascading() {
+        // each variable's flip changes the next one, which then triggers its own term
+        let chain = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        let mut cascading = chain.clone();
+        assert_eq!(
+            cascading.flip_cascading(0, 10),
+            CascadeOutcome::Settled { rounds: 2 }
+        );
+        assert_eq!(cascading.observables(), 0b111);
+        let mut flipped = chain.clone();
+        flipped.flip(0);
+        assert_eq!(flipped.observables(), 0b011);
+        let mut capped = chain.clone();
+        assert_eq!(capped.flip_cascading(0, 1), CascadeOutcome::Unsettled);
+        assert_eq!(capped.observables(), 0b111);
+
+        // 0 and 1 keep changing each other back and forth
+        let mut ring = Machine::new(2, |term| term.len() == 2);
+        assert_eq!(
+            ring.flip_cascading(0, 100),
+            CascadeOutcome::Oscillation { period: 4 }
+        );
+
+        for machine in Machine::all(2) {
+            let mut linear = machine.clone();
+            linear.truncate(1);
+            let mut flipped = linear.clone();
+            flipped.flip(1);
+            assert_eq!(
+                linear.flip_cascading(1, 0),
+                CascadeOutcome::Settled { rounds: 0 }
+            );
+            assert_eq!(linear, flipped);
         }
-        machines
     }
 
-    pub fn flip(&mut self, variable: usize) {
-        term_to_index(self.variables, |term_to_index| {
-            index_to_term(self.variables, |index_to_term| {
-                self.values[term_to_index[&vec![variable]]] ^= true;
-                let terms_to_flip = self
-                    .values
-                    .iter()
-                    .copied()
-                    .enumerate()
-                    .filter(|&(index, value)| index_to_term[index][0] == variable && value)
-                    .map(|(index, _)| index_to_term[index][1..].to_vec())
-                    .filter(|term| !term.is_empty())
-                    .collect_vec();
-                for term in terms_to_flip {
-                    self.values[term_to_index[&term]] ^= true;
-                }
-            });
-        });
+    #[test]
+    fn cascading_strategies() {
+        let outcomes = Machine::all(3)
+            .into_iter()
+            .map(|machine| {
+                [
+                    PropagationStrategy::Simultaneous,
+                    PropagationStrategy::Ascending,
+                    PropagationStrategy::Descending,
+                ]
+                .map(|strategy| {
+                    let mut machine = machine.clone().with_strategy(strategy);
+                    let outcome = machine.flip_cascading(0, 64);
+                    (outcome, machine.values().to_vec())
+                })
+            })
+            .collect_vec();
+        assert!(
+            outcomes
+                .iter()
+                .any(|[simultaneous, ascending, _]| simultaneous != ascending)
+        );
+        assert!(
+            outcomes
+                .iter()
+                .any(|[_, ascending, descending]| ascending != descending)
+        );
     }
 
-    pub fn get(&self, variable: usize) -> bool {
-        term_to_index(self.variables, |term_to_index| {
-            self.values[term_to_index[&vec![variable]]]
-        })
+    #[test]
+    fn set_many() {
+        let mut system = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        system.set_many(&[(0, true), (1, false)]);
+        assert_eq!(system.get(0), true);
+        assert_eq!(system.get(1), false);
+        system.set_many(&[(0, false), (1, true)]);
+        assert_eq!(system.get(0), false);
+        assert_eq!(system.get(1), true);
+
+        let mut sequential = Machine::new(2, |term| matches!(term, [0, 1] | [1, 0]));
+        sequential.set(0, true);
+        sequential.set(1, false);
+        assert_eq!(sequential.get(0), false);
     }
 
-    pub fn set(&mut self, variable: usize, value: bool) {
-        if self.get(variable) != value {
-            self.flip(variable);
+    #[test]
+    fn undo_redo() {
+        let initial = GenericMachine::<Gf3>::new(3, |term| match term {
+            [0, 1] | [1, 2, 0] => Gf3(1),
+            [0, 2] => Gf3(2),
+            _ => Gf3(0),
+        });
+        let mut system = initial.clone().into_recorded();
+        let mut states = vec![initial.clone()];
+        for variable in [0, 1, 0, 2, 0] {
+            system.flip_recorded(variable);
+            states.push((*system).clone());
+        }
+        assert_eq!(system.history(), [0, 1, 0, 2, 0]);
+        for (undone, expected) in [0, 2, 0, 1, 0].into_iter().zip(states.iter().rev().skip(1)) {
+            assert_eq!(system.undo(), Some(undone));
+            assert_eq!(&*system, expected);
         }
+        assert_eq!(system.undo(), None);
+        assert!(system.history().is_empty());
+
+        assert_eq!(system.redo(), Some(0));
+        assert_eq!(system.redo(), Some(1));
+        assert_eq!(&*system, &states[2]);
+        system.flip_recorded(2);
+        assert_eq!(system.redo(), None);
+        assert_eq!(system.history(), [0, 1, 2]);
+        system.undo();
+        assert_eq!(system.into_inner(), states[2]);
     }
-}
 
-impl Debug for Machine {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        index_to_term(self.variables, |index_to_term| {
-            let mut debug_map = f.debug_map();
-            for (term, value) in index_to_term.iter().zip(self.values.iter()) {
-                debug_map.entry(term, value);
+    #[test]
+    fn snapshots() {
+        let mut system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2, 0]));
+        let snapshot = system.snapshot();
+        assert_eq!(snapshot.words().len(), 1);
+        assert_eq!(system.changes_since(&snapshot), Ok(vec![]));
+        system.flip(1);
+        assert_eq!(
+            system.changes_since(&snapshot),
+            Ok(vec![Term::new([1]), Term::new([2, 0])])
+        );
+        let flipped = system.clone();
+        system.restore(&snapshot).unwrap();
+        assert_eq!(
+            system,
+            Machine::new(3, |term| matches!(term, [0, 1] | [1, 2, 0]))
+        );
+        system.restore(&flipped.snapshot()).unwrap();
+        assert_eq!(system, flipped);
+        assert_eq!(
+            system.restore(&Machine::new(2, |_| false).snapshot()),
+            Err(MachineError::VariablesMismatch { left: 3, right: 2 })
+        );
+    }
+
+    #[test]
+    fn observables() {
+        for mut machine in Machine::all(2) {
+            let expected = machine.get(0) as u64 | (machine.get(1) as u64) << 1;
+            assert_eq!(machine.observables(), expected);
+            for mask in 0..4 {
+                machine.set_observables(mask);
+                assert_eq!(machine.observables(), mask);
             }
-            debug_map.finish()
-        })
+        }
     }
-}
 
-fn factorial(n: usize) -> usize {
-    (1..=n).product()
-}
+    #[test]
+    fn observables_follow_mutations() {
+        fn check(machine: &Machine) {
+            let expected = (0..machine.variables())
+                .filter(|&variable| machine.get(variable))
+                .fold(0, |mask, variable| mask | 1 << variable);
+            assert_eq!(machine.observables(), expected);
+        }
 
-fn permutations(n: usize, k: usize) -> usize {
-    factorial(n) / factorial(n - k)
-}
+        let start = Machine::new(3, |term| matches!(term, [1] | [0, 1] | [1, 2] | [2, 0, 1]));
+        check(&start);
+        let mut machine = start.clone();
+        let snapshot = machine.snapshot();
+        for variable in [0, 2, 1, 0] {
+            machine.flip(variable);
+            check(&machine);
+        }
+        machine.flip_many(&[0, 1, 2]);
+        check(&machine);
+        machine.set_term(&Term::new([2]), true);
+        check(&machine);
+        machine.flip_term(&Term::new([0]));
+        check(&machine);
+        machine.flip_cascading(1, 4);
+        check(&machine);
+        machine.restore(&snapshot).unwrap();
+        check(&machine);
+        machine.truncate(0);
+        check(&machine);
+        assert_eq!(machine.observables(), 0);
 
-#[cfg(test)]
-mod tests {
-    use crate::Machine;
-    use hashlink::LinkedHashMap;
-    use itertools::Itertools;
-    use std::collections::BTreeSet;
+        let mut recorded = start.clone().into_recorded();
+        recorded.flip_recorded(0);
+        recorded.flip_recorded(1);
+        check(&recorded);
+        recorded.undo();
+        check(&recorded);
+        let mut resettable = start.into_resettable();
+        resettable.flip(1);
+        resettable.reset_to_initial();
+        check(&resettable);
+    }
 
     #[test]
-    fn print_terms() {
-        Machine::new(5, |term| {
-            println!("{term:?}");
-            true
-        });
+    fn invariants() {
+        // [0, 1] and [1, 0] only ever feed into the variables, and nothing feeds into them
+        let system = Machine::new(2, |term| term == [0, 1]);
+        let invariants = system.invariants();
+        assert!(invariants.constant_variables.is_empty());
+        assert_eq!(
+            invariants.constant_terms,
+            [(Term::new([0, 1]), true), (Term::new([1, 0]), false)]
+        );
+
+        // flipping 2 toggles [0, 1] whenever [2, 0, 1] is true
+        let system = Machine::new(3, |term| term == [2, 0, 1]);
+        let invariants = system.invariants();
+        assert!(
+            !invariants
+                .constant_terms
+                .contains(&(Term::new([0, 1]), false))
+        );
+        assert!(
+            invariants
+                .constant_terms
+                .contains(&(Term::new([2, 0, 1]), true))
+        );
+        assert!(
+            invariants
+                .constant_terms
+                .contains(&(Term::new([1, 0]), false))
+        );
     }
 
     #[test]
-    fn all() {
-        let variables = 3;
-        let machines = Machine::all(variables);
-        let to_index = machines
-            .iter()
-            .cloned()
-            .enumerate()
-            .map(|(i, m)| (m, i))
-            .collect::<LinkedHashMap<_, _>>();
-        let mut index_map = (0..machines.len()).collect_vec();
-        for machine in &machines {
-            for variable in 0..variables {
-                let mut clone = machine.clone();
-                clone.flip(variable);
+    fn behavioral_equivalence() {
+        // [0, 1] only shows once 0 is flipped
+        let plain = Machine::new(2, |_| false);
+        let coupled = Machine::new(2, |term| term == [0, 1]);
+        assert_eq!(plain.distinguishing_sequence(&coupled), Some(vec![0]));
+        assert!(!plain.behaviorally_equivalent(&coupled));
 
-                let machine_index = to_index[machine];
-                let clone_index = to_index[&clone];
-                
-                let first_index = index_map[machine_index];
-                let second_index = index_map[clone_index];
+        // [0, 1, 2] sets [1, 2] when 0 is flipped, which then shows when 1 is flipped
+        let deep = Machine::new(3, |term| term == [0, 1, 2]);
+        assert_eq!(
+            Machine::new(3, |_| false).distinguishing_sequence(&deep),
+            Some(vec![0, 1])
+        );
+        assert_eq!(plain.distinguishing_sequence(&deep), Some(vec![]));
 
-                if machine_index == 16272 {
-                    println!("{machine:?}, {variable} => {clone:?} ({first_index}, {second_index})");
-                }
+        // every term eventually shows, so bool machines are only equivalent to themselves
+        for (left, right) in Machine::all(2).iter().tuple_combinations() {
+            assert!(!left.behaviorally_equivalent(right));
+        }
+        assert!(deep.behaviorally_equivalent(&deep.clone()));
+        assert_eq!(deep.minimize(), deep);
+    }
 
-                if first_index < second_index {
-                    index_map[second_index] = first_index;
-                } else if second_index < first_index {
-                    index_map[first_index] = second_index;
-                }
+    #[test]
+    fn canonical_form() {
+        for machine in Machine::all(2).into_iter().chain([
+            Machine::new(3, |term| matches!(term, [2] | [2, 0] | [1, 2, 0])),
+            Machine::new(3, |term| term.len() == 2 && term[0] == 1),
+        ]) {
+            let (canonical, relabeling) = machine.canonical_form();
+            assert_eq!(machine.permute_variables(&relabeling), canonical);
+            assert_eq!(machine.canonicalize(), canonical);
+            let relabeled =
+                machine.permute_variables(&(0..machine.variables()).rev().collect_vec());
+            assert_eq!(relabeled.canonical_form().0, canonical);
+        }
+        let system = Machine::new(2, |term| term == [0]);
+        assert_eq!(
+            system.canonical_form(),
+            (Machine::new(2, |term| term == [1]), vec![1, 0])
+        );
+    }
+
+    #[test]
+    fn pointwise_algebra() {
+        let left = Machine::new(2, |term| matches!(term, [0] | [0, 1]));
+        let right = Machine::new(2, |term| matches!(term, [0] | [1, 0]));
+        let expect = |terms: &[&[usize]]| Machine::new(2, |term| terms.contains(&term));
+        assert_eq!(left.xor(&right), Ok(expect(&[&[0, 1], &[1, 0]])));
+        assert_eq!(left.and(&right), Ok(expect(&[&[0]])));
+        assert_eq!(left.or(&right), Ok(expect(&[&[0], &[0, 1], &[1, 0]])));
+        assert_eq!(left.negate_terms(), expect(&[&[1], &[1, 0]]));
+        assert_eq!(left.negate_terms().negate_terms(), left);
+        assert_eq!(
+            left.xor(&Machine::new(3, |_| false)),
+            Err(MachineError::VariablesMismatch { left: 2, right: 3 })
+        );
+
+        // flipping is affine: flipping both components changes their XOR only in the flipped
+        // variable, which gets `ONE` added twice
+        for (left, right) in Machine::all(2).iter().tuple_combinations() {
+            for variable in 0..2 {
+                let mut combined = left.xor(right).unwrap();
+                combined.flip(variable);
+                let (mut left, mut right) = (left.clone(), right.clone());
+                left.flip(variable);
+                right.flip(variable);
+                combined.flip_term(&Term::new([variable]));
+                assert_eq!(left.xor(&right), Ok(combined));
             }
         }
-        let mut reverse_index_map = (0..machines.len()).map(|_| Vec::new()).collect_vec();
-        for (machine_index, prime_index) in index_map.iter().copied().enumerate() {
-            reverse_index_map[prime_index].push(machine_index);
+    }
+
+    #[test]
+    fn structural_predicates() {
+        let empty = Machine::new(3, |_| false);
+        assert!(empty.is_linear() && empty.is_symmetric());
+        assert_eq!(empty.max_order(), 0);
+
+        let linear = Machine::new(3, |term| term == [1]);
+        assert!(linear.is_linear() && linear.is_symmetric());
+        assert_eq!(linear.max_order(), 1);
+
+        let pairs = Machine::new(3, |term| term.len() == 2 && term.contains(&0));
+        assert!(!pairs.is_linear() && pairs.is_symmetric());
+        assert_eq!(pairs.max_order(), 2);
+
+        let ordered = Machine::new(3, |term| matches!(term, [0] | [2, 1, 0]));
+        assert!(!ordered.is_linear() && !ordered.is_symmetric());
+        assert_eq!(ordered.max_order(), 3);
+
+        assert!(Machine::new(3, |_| true).is_symmetric());
+        let gf3 = GenericMachine::new(2, |term| if term == [0, 1] { Gf3(2) } else { Gf3(0) });
+        assert_eq!(gf3.max_order(), 2);
+        assert!(!gf3.is_symmetric());
+    }
+
+    #[test]
+    fn bounded_order() {
+        let producer = |term: &[usize]| matches!(term, [1] | [0, 1] | [1, 2] | [2, 1, 0]);
+        let mut truncated = Machine::new(3, producer);
+        truncated.truncate(2);
+        assert_eq!(truncated.max_order(), 2);
+        assert!(!truncated.get_term(&Term::new([2, 1, 0])));
+        assert!(truncated.get_term(&Term::new([1, 2])));
+        truncated.truncate(0);
+        assert_eq!(truncated, Machine::new(3, |_| false));
+
+        let mut longest = 0;
+        let mut bounded = Machine::new_bounded(3, 2, |term| {
+            longest = longest.max(term.len());
+            producer(term)
+        });
+        assert_eq!(longest, 2);
+        assert_eq!(bounded.values().len(), 3 + 6);
+        assert_eq!(bounded.table().max_order(), 2);
+        let mut truncated = Machine::new(3, producer);
+        truncated.truncate(2);
+        for variable in [0, 2, 1, 1, 0] {
+            bounded.flip(variable);
+            truncated.flip(variable);
+            assert_eq!(
+                bounded.terms().collect_vec(),
+                truncated.terms().take(9).collect_vec()
+            );
         }
-        let prime_machine_indices = index_map.iter().copied().collect::<BTreeSet<_>>();
-        println!("{:?}", prime_machine_indices);
-        println!("{:?}", prime_machine_indices.len());
-        println!(
-            "{:#?}",
-            prime_machine_indices
-                .iter()
-                .rev()
-                .min_by_key(|index| reverse_index_map[**index].len())
-                .map(|index| (index, reverse_index_map[*index].len(), &machines[*index]))
+        assert_eq!(
+            Machine::new_bounded(2, 5, producer),
+            Machine::new(2, producer)
         );
-        // for machine_index in prime_machine_indices {
-        //     let machine = &machines[machine_index];
-        //     let size = reverse_index_map[machine_index].len();
-        //     println!("{:?}", size);
-        // }
     }
 
     #[test]
-    fn three() {
-        let mut system = Machine::new(3, |term| match term {
-            [0] => false,
-            [1] => true,
-            [2] => true,
-            [0, 1] => true,
-            [0, 2] => true,
-            [1, 0] => true,
-            [1, 2] => true,
-            [2, 0] => true,
-            [2, 1] => false,
-            [0, 1, 2] => false,
-            [0, 2, 1] => true,
-            [1, 0, 2] => false,
-            [1, 2, 0] => false,
-            [2, 0, 1] => false,
-            [2, 1, 0] => false,
-            _ => panic!(),
-        });
-        println!("{:?}", system);
-        system.flip(0);
-        println!("{:?}", system);
+    fn bounded_storage() {
+        // pairwise terms of 10 variables, instead of the almost ten million terms of the full table
+        let mut wide = Machine::new_bounded(10, 2, |term| term == [0, 9] || term == [9, 3]);
+        assert_eq!(wide.values().len(), 10 + 90);
+        wide.flip(0);
+        wide.flip(9);
+        assert_eq!(wide.observables(), 0b1001);
+        assert!(!wide.get_term(&Term::new([0, 9, 3])));
+        assert!(format!("{wide:?}").ends_with("[9, 8]: false}"));
+
+        let pairs = Machine::new_bounded(3, 2, |term| term == [0, 1]);
+        let mut full = Machine::new(3, |term| term == [0, 1]);
+        assert_eq!(
+            pairs.diff(&full),
+            Err(MachineError::MaxOrderMismatch { left: 2, right: 3 })
+        );
+        assert_eq!(
+            pairs.xor(&full),
+            Err(MachineError::MaxOrderMismatch { left: 2, right: 3 })
+        );
+        assert!(!pairs.is_isomorphic(&full));
+        assert_eq!(pairs.is_isomorphic_to(&full), None);
+        assert_eq!(
+            full.restore(&pairs.snapshot()),
+            Err(MachineError::MaxOrderMismatch { left: 3, right: 2 })
+        );
+        assert_eq!(
+            pairs.is_isomorphic_to(&pairs.permute_variables(&[2, 0, 1])),
+            Some(vec![2, 0, 1])
+        );
+
+        let restricted = pairs.restrict(&[1, 0]);
+        assert_eq!(restricted.table().max_order(), 2);
+        assert!(restricted.get_term(&Term::new([1, 0])));
+        let composed = pairs.compose_with(&Machine::new(1, |_| true), |term| term == [3, 0]);
+        assert_eq!(composed.table().max_order(), 2);
+        assert_eq!(
+            composed,
+            Machine::new_bounded(4, 2, |term| matches!(term, [0, 1] | [3] | [3, 0]))
+        );
+        assert!(!full.compose(&full).table().is_bounded());
+
+        assert_eq!(
+            Machine::builder(3).max_order(2).term(&[0, 1], true).build(),
+            Ok(pairs)
+        );
+        assert_eq!(
+            Machine::builder(3)
+                .max_order(2)
+                .term(&[0, 1, 2], true)
+                .build(),
+            Err(MachineError::TermBeyondMaxOrder {
+                term: vec![0, 1, 2],
+                max_order: 2
+            })
+        );
+        assert_eq!(
+            Machine::builder(2).max_order(0).term(&[0], true).build(),
+            Ok(Machine::new_bounded(2, 0, |term| term == [0]))
+        );
     }
 
     #[test]
-    fn experimental() {
-        let mut system = Machine::new(2, |term| match term {
-            [0] => false,
-            [1] => false,
-            [0, 1] => true,
-            [1, 0] => false,
-            _ => panic!(),
-        });
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
-        system.set(0, true);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), true);
-        system.set(0, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
-        system.set(1, true);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
-        system.set(1, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
-        system.set(1, true);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
-        system.set(0, true);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), false);
-        system.set(1, false);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), false);
-        system.set(0, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
+    fn automorphisms() {
+        for machine in Machine::all(3).into_iter().step_by(97) {
+            let expected = (0..3)
+                .permutations(3)
+                .filter(|relabeling| machine.permute_variables(relabeling) == machine)
+                .collect_vec();
+            assert_eq!(machine.automorphisms(), expected);
+        }
+        assert_eq!(Machine::new(4, |_| false).automorphisms().len(), 24);
+        let cycle = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2] | [2, 0]));
+        assert_eq!(
+            cycle.automorphisms(),
+            vec![vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1]]
+        );
+        let pairs = Machine::new_bounded(5, 2, |term| term == [0, 1] || term == [1, 0]);
+        assert_eq!(pairs.automorphisms().len(), 2 * 6);
+        assert_eq!(
+            Machine::new(0, |_| false).automorphisms(),
+            vec![Vec::<usize>::new()]
+        );
     }
 
     #[test]
-    fn equals() {
-        let mut system = Machine::new(2, |term| match term {
-            [0] => false,
-            [1] => false,
-            [0, 1] => true,
-            [1, 0] => true,
-            _ => panic!(),
-        });
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
-        system.set(0, true);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), true);
-        system.set(0, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
-        system.set(1, true);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), true);
-        system.set(1, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), false);
+    fn embeds_in() {
+        let equals = Machine::new(2, |term| term.len() == 2);
+        let not_equals = Machine::new(2, |term| term != [0]);
+        let mut system = Machine::new(4, |term| matches!(term, [0] | [3, 1] | [1, 3] | [0, 2]));
+        assert_eq!(equals.embeds_in(&system), Some(vec![1, 3]));
+        assert_eq!(not_equals.embeds_in(&system), None);
+        system.set_term(&Term::new([1]), true);
+        assert_eq!(equals.embeds_in(&system), None);
+        let mapping = not_equals.embeds_in(&system).unwrap();
+        assert_eq!(mapping, [3, 1]);
+        assert_eq!(system.restrict(&mapping), not_equals);
+
+        let bounded = Machine::new_bounded(5, 2, |term| term == [4, 2] || term == [2, 4]);
+        assert_eq!(equals.embeds_in(&bounded), Some(vec![2, 4]));
+        assert_eq!(system.embeds_in(&equals), None);
+        assert_eq!(Machine::new(0, |_| false).embeds_in(&equals), Some(vec![]));
     }
 
     #[test]
-    fn not_equals() {
-        let mut system = Machine::new(2, |term| match term {
-            [0] => false,
-            [1] => true,
-            [0, 1] => true,
-            [1, 0] => true,
-            _ => panic!(),
+    fn solve() {
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        assert_eq!(system.solve(&[None, None, None]), Some(vec![]));
+        // BFS tries flipping 1 before 2, and [1, 2] makes that set 2 as well
+        assert_eq!(system.solve(&[None, None, Some(true)]), Some(vec![1]));
+        assert_eq!(
+            system.solve(&[None, Some(true), Some(false)]),
+            Some(vec![0])
+        );
+        let solution = system
+            .solve(&[Some(false), Some(false), Some(true)])
+            .unwrap();
+        let mut machine = system.clone();
+        for &variable in &solution {
+            machine.flip(variable);
+        }
+        assert_eq!(machine.observables(), 0b100);
+
+        // flipping either variable flips both, so they can never differ
+        let locked = Machine::new(2, |term| term.len() == 2);
+        assert_eq!(locked.solve(&[Some(true), Some(true)]), Some(vec![0]));
+        assert_eq!(locked.solve(&[Some(true), Some(false)]), None);
+        let gf3 = GenericMachine::new(1, |_| Gf3(0));
+        assert_eq!(gf3.solve(&[Some(Gf3(2))]), Some(vec![0, 0]));
+        let independent = GenericMachine::new(2, |_| Gf3(0));
+        assert_eq!(
+            independent.solve(&[Some(Gf3(1)), Some(Gf3(1))]),
+            Some(vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn thread_safety() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<Machine>();
+        assert_send_sync::<GenericMachine<Gf3>>();
+        assert_send_sync::<TermTable>();
+
+        // clones moved to other threads keep sharing one table
+        let machine = Machine::new(4, |term| term.len() == 2);
+        std::thread::scope(|scope| {
+            for variable in 0..4 {
+                let mut clone = machine.clone();
+                let table = machine.table();
+                scope.spawn(move || {
+                    clone.flip(variable);
+                    assert!(Arc::ptr_eq(clone.table(), table));
+                });
+            }
         });
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
-        system.set(0, true);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), false);
-        system.set(0, false);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
-        system.set(1, true);
-        assert_eq!(system.get(0), false);
-        assert_eq!(system.get(1), true);
-        system.set(1, false);
-        assert_eq!(system.get(0), true);
-        assert_eq!(system.get(1), false);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_search() {
+        let system = Machine::new(4, |term| matches!(term, [0, 1] | [1, 2] | [2, 3, 0]));
+        for target in 0..16 {
+            let predicate = |machine: &Machine| machine.observables() == target;
+            assert_eq!(
+                system.par_search(predicate),
+                system
+                    .reachable_states()
+                    .find(|(machine, _)| predicate(machine))
+                    .map(|(_, flips)| flips)
+            );
+        }
+        let gf3 = GenericMachine::new(1, |_| Gf3(0));
+        assert_eq!(
+            gf3.par_search(|machine| machine.get(0) == Gf3(2)),
+            Some(vec![0, 0])
+        );
     }
 }
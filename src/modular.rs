@@ -0,0 +1,121 @@
+use crate::Value;
+use core::fmt::{Display, Formatter};
+
+/// The integers modulo `K`, as machine values: `flip` adds one modulo `K`, so a variable returns
+/// to its value after `K` flips, and cascades add term values modulo `K`. `Modular<2>` behaves
+/// like `bool`.
+///
+/// Using `Value::ONE` with a `K` below 2 is a compile-time error.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Modular<const K: u8>(u8);
+
+impl<const K: u8> Modular<K> {
+    /// The residue of `value` modulo `K`.
+    pub fn new(value: u8) -> Self {
+        Self(value % K)
+    }
+
+    /// The representative in `0..K`.
+    pub fn value(self) -> u8 {
+        self.0
+    }
+
+    /// All `K` values, in ascending order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..K).map(Self)
+    }
+}
+
+impl<const K: u8> Value for Modular<K> {
+    const ZERO: Self = Self(0);
+    const ONE: Self = {
+        assert!(K >= 2, "the modulus must be at least 2");
+        Self(1)
+    };
+
+    fn add(self, other: Self) -> Self {
+        Self(((self.0 as u16 + other.0 as u16) % K as u16) as u8)
+    }
+}
+
+impl<const K: u8> From<Modular<K>> for u8 {
+    fn from(value: Modular<K>) -> Self {
+        value.0
+    }
+}
+
+impl<const K: u8> Display for Modular<K> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenericMachine, Machine, Modular, Value};
+
+    type Z3 = Modular<3>;
+    type Z4 = Modular<4>;
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Z4::new(7), Z4::new(3));
+        assert_eq!(Z4::new(3).add(Z4::new(2)).value(), 1);
+        assert_eq!(Modular::<255>::new(254).add(Modular::new(254)).value(), 253);
+        assert_eq!(Z3::all().map(u8::from).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(Z3::ONE.to_string(), "1");
+    }
+
+    #[test]
+    fn ternary_machine() {
+        let mut machine = GenericMachine::new(2, |term| match term {
+            [0, 1] => Z3::new(2),
+            _ => Z3::ZERO,
+        });
+        let initial = machine.clone();
+        machine.flip(0);
+        assert_eq!((machine.get(0), machine.get(1)), (Z3::new(1), Z3::new(2)));
+        machine.flip(0);
+        machine.flip(0);
+        assert_eq!(machine, initial);
+        machine.set(1, Z3::new(1));
+        assert_eq!(machine.get(1), Z3::new(1));
+        // the flips of 0 and 1 move the variables independently
+        assert_eq!(initial.reachable().len(), 9);
+    }
+
+    #[test]
+    fn binary_like_bool() {
+        for machine in Machine::all(2) {
+            let modular = GenericMachine::new(2, |term| {
+                Modular::<2>::new(machine.get_term(&term.into()) as u8)
+            });
+            for variable in 0..2 {
+                let (mut machine, mut modular) = (machine.clone(), modular.clone());
+                machine.flip(variable);
+                modular.flip(variable);
+                assert!(
+                    machine
+                        .values()
+                        .iter()
+                        .zip(modular.values())
+                        .all(|(&bit, value)| bit as u8 == value.value())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn quaternary_cascade() {
+        let mut machine = GenericMachine::new(3, |term| match term {
+            [0, 1] => Z4::new(3),
+            [0, 2] => Z4::new(2),
+            _ => Z4::ZERO,
+        });
+        machine.flip(0);
+        machine.flip(0);
+        assert_eq!(machine.get(0), Z4::new(2));
+        assert_eq!(machine.get(1), Z4::new(2));
+        assert_eq!(machine.get(2), Z4::new(0));
+    }
+}
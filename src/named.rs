@@ -0,0 +1,238 @@
+//! Machines whose variables have names, which are used instead of the indices in the accessors and
+//! in `Debug`, `Display` and the text format.
+//!
+//! The text format of a named machine is that of `text`, with the names of a term's variables
+//! separated by `.`, e.g. `clock:-, reset:+, clock.reset:-, reset.clock:+`. Names must therefore be
+//! non-empty and must not contain whitespace, `.`, `,` or `:`.
+
+use crate::collections::HashMap;
+use crate::pretty::Pretty;
+use crate::text::{self, ParseMachineError};
+use crate::{Machine, MachineError, Term};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+use core::ops::Deref;
+use itertools::Itertools;
+
+/// A machine together with a name for each of its variables, created by `Machine::with_names`.
+///
+/// Only shared access to the machine is given out, so that its names always match its variables.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct NamedMachine {
+    machine: Machine,
+    names: Vec<String>,
+}
+
+impl NamedMachine {
+    pub(crate) fn new(machine: Machine, names: &[&str]) -> Result<Self, MachineError> {
+        if names.len() != machine.variables() {
+            return Err(MachineError::NameCountMismatch {
+                names: names.len(),
+                variables: machine.variables(),
+            });
+        }
+        for (index, &name) in names.iter().enumerate() {
+            if name.is_empty()
+                || name
+                    .chars()
+                    .any(|c| c.is_whitespace() || matches!(c, '.' | ',' | ':'))
+            {
+                return Err(MachineError::InvalidName(name.to_string()));
+            }
+            if names[..index].contains(&name) {
+                return Err(MachineError::DuplicateName(name.to_string()));
+            }
+        }
+        Ok(Self {
+            machine,
+            names: names.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    /// Reads a machine in the named text format. Unlike `Machine::from_str`, this takes the
+    /// variables from `names` rather than from the text, so variables need not be mentioned.
+    pub fn parse(text: &str, names: &[&str]) -> Result<Self, ParseMachineError> {
+        let indices = names
+            .iter()
+            .enumerate()
+            .map(|(index, &name)| (name, index))
+            .collect::<HashMap<_, _>>();
+        let values = text::parse_entries(text, |term| {
+            term.split('.')
+                .map(|name| indices.get(name).copied())
+                .collect()
+        })?;
+        Ok(Self::new(text::build(names.len(), values)?, names)?)
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self, variable: usize) -> &str {
+        &self.names[variable]
+    }
+
+    /// The variable with the given name, if there is one.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|other| other == name)
+    }
+
+    /// Panics if there is no variable with the given name.
+    pub fn get_named(&self, name: &str) -> bool {
+        self.machine.get(self.expect_index(name))
+    }
+
+    /// Panics if there is no variable with the given name.
+    pub fn flip_named(&mut self, name: &str) {
+        let variable = self.expect_index(name);
+        self.machine.flip(variable);
+    }
+
+    /// Panics if there is no variable with the given name.
+    pub fn set_named(&mut self, name: &str, value: bool) {
+        let variable = self.expect_index(name);
+        self.machine.set(variable, value);
+    }
+
+    pub fn flip(&mut self, variable: usize) {
+        self.machine.flip(variable);
+    }
+
+    pub fn set(&mut self, variable: usize, value: bool) {
+        self.machine.set(variable, value);
+    }
+
+    /// The term with the names of its variables, separated by `.`.
+    pub fn term_name(&self, term: &Term) -> String {
+        term.iter().map(|&variable| &self.names[variable]).join(".")
+    }
+
+    /// The pretty layout of the machine, with its variables written by name.
+    pub fn pretty(&self) -> Pretty<'_> {
+        self.machine.pretty().names(&self.names)
+    }
+
+    pub fn into_inner(self) -> Machine {
+        self.machine
+    }
+
+    pub fn into_parts(self) -> (Machine, Vec<String>) {
+        (self.machine, self.names)
+    }
+
+    fn expect_index(&self, name: &str) -> usize {
+        self.index_of(name)
+            .unwrap_or_else(|| panic!("there is no variable named {name:?}"))
+    }
+}
+
+impl Deref for NamedMachine {
+    type Target = Machine;
+
+    fn deref(&self) -> &Self::Target {
+        &self.machine
+    }
+}
+
+impl Debug for NamedMachine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut debug_map = f.debug_map();
+        for (term, value) in self.machine.terms() {
+            debug_map.entry(&self.term_name(term), &value);
+        }
+        debug_map.finish()
+    }
+}
+
+impl Display for NamedMachine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let entries = self.machine.terms().map(|(term, value)| {
+            format!("{}:{}", self.term_name(term), if value { '+' } else { '-' })
+        });
+        write!(f, "{}", entries.format(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::named::NamedMachine;
+    use crate::text::ParseMachineError;
+    use crate::{Machine, MachineError};
+
+    #[test]
+    fn accessors() {
+        let mut machine = Machine::new(3, |term| term == [0, 2])
+            .with_names(&["clock", "reset", "q"])
+            .unwrap();
+        assert_eq!(machine.index_of("q"), Some(2));
+        assert_eq!(machine.index_of("d"), None);
+        assert_eq!(machine.name(1), "reset");
+        machine.flip_named("clock");
+        assert!(machine.get_named("q"));
+        machine.set_named("reset", true);
+        machine.set_named("reset", true);
+        assert_eq!(machine.observables(), 0b111);
+        assert_eq!(
+            machine.pretty().only_true().to_string(),
+            "order 1 clock reset q\norder 2 clock.q"
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        let machine = Machine::new(2, |_| false);
+        assert_eq!(
+            machine.clone().with_names(&["a"]),
+            Err(MachineError::NameCountMismatch {
+                names: 1,
+                variables: 2
+            })
+        );
+        assert_eq!(
+            machine.clone().with_names(&["a", "a"]),
+            Err(MachineError::DuplicateName("a".to_string()))
+        );
+        for name in ["", "a.b", "a b", "a:", "a,b"] {
+            assert_eq!(
+                machine.clone().with_names(&["x", name]),
+                Err(MachineError::InvalidName(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn unknown_name() {
+        let machine = Machine::new(1, |_| false).with_names(&["a"]).unwrap();
+        machine.get_named("b");
+    }
+
+    #[test]
+    fn text() {
+        let machine = Machine::new(2, |term| matches!(term, [1] | [0, 1]))
+            .with_names(&["in", "out"])
+            .unwrap();
+        let text = "in:-, out:+, in.out:+, out.in:-";
+        assert_eq!(machine.to_string(), text);
+        assert_eq!(
+            format!("{machine:?}"),
+            r#"{"in": false, "out": true, "in.out": true, "out.in": false}"#
+        );
+        let names = ["in", "out"];
+        assert_eq!(NamedMachine::parse(text, &names), Ok(machine.clone()));
+        assert_eq!(NamedMachine::parse("out:+, in.out:+", &names), Ok(machine));
+        assert_eq!(
+            NamedMachine::parse("in.x:+", &names),
+            Err(ParseMachineError::InvalidTerm("in.x:+".to_string()))
+        );
+        assert_eq!(
+            NamedMachine::parse("in.in:+", &names),
+            Err(ParseMachineError::Machine(MachineError::MalformedTerm(
+                vec![0, 0]
+            )))
+        );
+    }
+}
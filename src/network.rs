@@ -0,0 +1,235 @@
+//! Networks of machines coupled by wires, each of which copies the value of one machine's variable
+//! to a variable of another whenever it changes.
+
+use crate::Machine;
+use crate::collections::HashMap;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// One end of a wire: a variable of one of the network's machines.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Port {
+    pub machine: usize,
+    pub variable: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Wire {
+    pub from: Port,
+    pub to: Port,
+}
+
+/// How propagating changes through a network ended.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Settlement {
+    /// No change is left to propagate, after `steps` changes were propagated.
+    FixedPoint { steps: usize },
+    /// The network returned to an earlier state with the same changes left to propagate, so it
+    /// keeps changing forever, repeating every `period` steps.
+    Oscillation { period: usize },
+}
+
+/// Machines and the wires between them. Whenever a variable at the start of a wire changes, the
+/// variable at its end is `set` to the new value, which may change further variables in turn.
+///
+/// Changes are propagated one at a time, first come first served, and each propagation step sets
+/// the ends of all wires starting at the changed variable, in the order the wires were added. As a
+/// step copies the variable's value at that time, a variable that changes again while its change
+/// is pending is not queued a second time.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Network {
+    machines: Vec<Machine>,
+    wires: Vec<Wire>,
+}
+
+impl Network {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a machine and returns its index.
+    pub fn add_machine(&mut self, machine: Machine) -> usize {
+        self.machines.push(machine);
+        self.machines.len() - 1
+    }
+
+    /// Adds a wire from `from` to `to`. Existing values are not copied along it; only later
+    /// changes are.
+    ///
+    /// Panics if either end is not a variable of one of the network's machines.
+    pub fn connect(&mut self, from: Port, to: Port) {
+        for port in [from, to] {
+            self.check_port(port);
+        }
+        self.wires.push(Wire { from, to });
+    }
+
+    pub fn machines(&self) -> &[Machine] {
+        &self.machines
+    }
+
+    pub fn machine(&self, index: usize) -> &Machine {
+        &self.machines[index]
+    }
+
+    pub fn wires(&self) -> &[Wire] {
+        &self.wires
+    }
+
+    pub fn get(&self, port: Port) -> bool {
+        self.machines[port.machine].get(port.variable)
+    }
+
+    /// Flips a variable and propagates the resulting changes. If the network oscillates, it is left
+    /// in the state in which the oscillation was detected.
+    ///
+    /// Panics if `port` is not a variable of one of the network's machines.
+    pub fn flip(&mut self, port: Port) -> Settlement {
+        self.check_port(port);
+        let mut changes = VecDeque::new();
+        self.flip_recording(port, &mut changes);
+        self.propagate(changes)
+    }
+
+    /// Sets a variable, as by `flip` if it does not have the given value yet.
+    ///
+    /// Panics if `port` is not a variable of one of the network's machines.
+    pub fn set(&mut self, port: Port, value: bool) -> Settlement {
+        self.check_port(port);
+        if self.get(port) == value {
+            return Settlement::FixedPoint { steps: 0 };
+        }
+        self.flip(port)
+    }
+
+    fn propagate(&mut self, mut changes: VecDeque<Port>) -> Settlement {
+        // with at most one pending change per variable, the states are finitely many, so the
+        // network either settles or cycles, which Brent's algorithm detects without storing every
+        // state
+        let mut power = 1;
+        let mut length = 0;
+        let mut tortoise = (self.machines.clone(), changes.clone());
+        for step in 0.. {
+            let Some(changed) = changes.pop_front() else {
+                return Settlement::FixedPoint { steps: step };
+            };
+            let value = self.get(changed);
+            for index in 0..self.wires.len() {
+                let Wire { from, to } = self.wires[index];
+                if from == changed && self.get(to) != value {
+                    self.flip_recording(to, &mut changes);
+                }
+            }
+            length += 1;
+            if self.machines == tortoise.0 && changes == tortoise.1 {
+                return Settlement::Oscillation { period: length };
+            }
+            if length == power {
+                tortoise = (self.machines.clone(), changes.clone());
+                power *= 2;
+                length = 0;
+            }
+        }
+        unreachable!()
+    }
+
+    // flips `port` and queues every variable of its machine that changed, unless a change of it is
+    // already pending
+    fn flip_recording(&mut self, port: Port, changes: &mut VecDeque<Port>) {
+        let trace = self.machines[port.machine].flip_traced(port.variable);
+        for term in trace.into_iter().filter(|term| term.len() == 1) {
+            let changed = Port {
+                machine: port.machine,
+                variable: term[0],
+            };
+            if !changes.contains(&changed) {
+                changes.push_back(changed);
+            }
+        }
+    }
+
+    fn check_port(&self, port: Port) {
+        assert!(
+            self.machines
+                .get(port.machine)
+                .is_some_and(|machine| port.variable < machine.variables()),
+            "{port:?} is not a variable of the network"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::{Network, Port, Settlement};
+    use crate::{Machine, Term};
+
+    fn port(machine: usize, variable: usize) -> Port {
+        Port { machine, variable }
+    }
+
+    #[test]
+    fn chain() {
+        // flipping 0 of the first machine changes its 1, which is copied to 0 of the second
+        // machine, where it changes 1 as well
+        let coupled = Machine::new(2, |term| term == [0, 1]);
+        let mut network = Network::new();
+        let first = network.add_machine(coupled.clone());
+        let second = network.add_machine(coupled);
+        network.connect(port(first, 1), port(second, 0));
+        assert_eq!(
+            network.flip(port(first, 0)),
+            Settlement::FixedPoint { steps: 4 }
+        );
+        assert!(network.get(port(second, 1)));
+        assert_eq!(
+            network.set(port(first, 0), true),
+            Settlement::FixedPoint { steps: 0 }
+        );
+        assert_eq!(network.wires().len(), 1);
+    }
+
+    #[test]
+    fn oscillation() {
+        // flipping 0 flips 1 as well, and 1 is fed back into 0
+        let mut network = Network::new();
+        let machine = network.add_machine(Machine::new(2, |term| term == [0, 1]));
+        network.connect(port(machine, 1), port(machine, 0));
+        let mut inverted = Machine::new(2, |term| term == [0, 1]);
+        inverted.set_term(&Term::new([1]), true);
+        let other = network.add_machine(inverted);
+        network.connect(port(other, 1), port(other, 0));
+
+        // 0 and 1 change together, so copying 1 to 0 leaves nothing to do
+        assert_eq!(
+            network.flip(port(machine, 0)),
+            Settlement::FixedPoint { steps: 2 }
+        );
+        // 0 and 1 start out different, so copying 1 to 0 flips both again and again
+        assert_eq!(
+            network.flip(port(other, 0)),
+            Settlement::Oscillation { period: 4 }
+        );
+    }
+
+    #[test]
+    fn feedback_oscillation() {
+        // every flip changes variable 2, whose wire back to 0 changes it again, so changes would
+        // pile up without bound if each were queued
+        let mut network = Network::new();
+        let machine = network.add_machine(Machine::from_signature(3, 13306).unwrap());
+        network.connect(port(machine, 2), port(machine, 0));
+        network.connect(port(machine, 2), port(machine, 2));
+        assert_eq!(
+            network.flip(port(machine, 1)),
+            Settlement::Oscillation { period: 5 }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_port() {
+        let mut network = Network::new();
+        network.add_machine(Machine::new(2, |_| false));
+        network.connect(port(0, 0), port(0, 2));
+    }
+}
@@ -0,0 +1,122 @@
+//! Callbacks that are notified of every change a flip makes, so that a machine can drive a GUI, a
+//! logger or another simulation without polling its whole state.
+
+use crate::{GenericMachine, Term, Value};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+use core::ops::Deref;
+
+type VariableCallback<'a, T> = Box<dyn FnMut(usize, T) + 'a>;
+type TermCallback<'a, T> = Box<dyn FnMut(&Term, T) + 'a>;
+
+/// A machine that calls its registered callbacks during `flip` propagation, created by
+/// `GenericMachine::into_observed`. Callbacks are called in the order in which the changes are
+/// made and, for each change, in the order in which they were registered.
+///
+/// Only shared access to the machine is given out, so no change can bypass the callbacks.
+pub struct ObservedMachine<'a, T: Value> {
+    machine: GenericMachine<T>,
+    variable_callbacks: Vec<VariableCallback<'a, T>>,
+    term_callbacks: Vec<TermCallback<'a, T>>,
+}
+
+impl<'a, T: Value> ObservedMachine<'a, T> {
+    pub(crate) fn new(machine: GenericMachine<T>) -> Self {
+        Self {
+            machine,
+            variable_callbacks: Vec::new(),
+            term_callbacks: Vec::new(),
+        }
+    }
+
+    /// Registers a callback that is called with a variable and its new value whenever a flip
+    /// changes it.
+    pub fn on_variable_changed(&mut self, callback: impl FnMut(usize, T) + 'a) {
+        self.variable_callbacks.push(Box::new(callback));
+    }
+
+    /// Registers a callback that is called with a term and its new value whenever a flip changes
+    /// it, including the single-variable terms.
+    pub fn on_term_changed(&mut self, callback: impl FnMut(&Term, T) + 'a) {
+        self.term_callbacks.push(Box::new(callback));
+    }
+
+    pub fn flip(&mut self, variable: usize) {
+        let Self {
+            machine,
+            variable_callbacks,
+            term_callbacks,
+        } = self;
+        machine.flip_observed(variable, |term, value| {
+            if let [variable] = **term {
+                for callback in variable_callbacks.iter_mut() {
+                    callback(variable, value);
+                }
+            }
+            for callback in term_callbacks.iter_mut() {
+                callback(term, value);
+            }
+        });
+    }
+
+    /// Flips `variable` until it has the given value, as by `GenericMachine::set`.
+    pub fn set(&mut self, variable: usize, value: T) {
+        while self.machine.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    pub fn into_inner(self) -> GenericMachine<T> {
+        self.machine
+    }
+}
+
+impl<T: Value> Deref for ObservedMachine<'_, T> {
+    type Target = GenericMachine<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.machine
+    }
+}
+
+impl<T: Value> Debug for ObservedMachine<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ObservedMachine")
+            .field("machine", &self.machine)
+            .field("variable_callbacks", &self.variable_callbacks.len())
+            .field("term_callbacks", &self.term_callbacks.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Machine, Term};
+
+    #[test]
+    fn callbacks() {
+        let mut variables = Vec::new();
+        let mut terms = Vec::new();
+        {
+            let mut system =
+                Machine::new(3, |term| matches!(term, [0, 1] | [0, 1, 2])).into_observed();
+            system.on_variable_changed(|variable, value| variables.push((variable, value)));
+            system.on_term_changed(|term, value| terms.push((term.clone(), value)));
+            system.flip(0);
+            system.set(1, true);
+            system.set(2, true);
+            assert_eq!(system.observables(), 0b111);
+        }
+        assert_eq!(variables, [(0, true), (1, true), (2, true)]);
+        assert_eq!(
+            terms,
+            [
+                (Term::new([0]), true),
+                (Term::new([1]), true),
+                (Term::new([1, 2]), true),
+                (Term::new([2]), true),
+            ]
+        );
+    }
+}
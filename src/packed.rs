@@ -0,0 +1,182 @@
+use crate::{DifferentialSystem, Machine, PropagationStrategy, Term, TermTable};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A `bool` machine storing its term values as bits, 64 to a word, in the same term order as
+/// `Machine`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PackedMachine {
+    table: Arc<TermTable>,
+    words: Vec<u64>,
+}
+
+impl PackedMachine {
+    pub fn new(variables: usize, initial_values_producer: impl FnMut(&[usize]) -> bool) -> Self {
+        Self::from(&Machine::new(variables, initial_values_producer))
+    }
+
+    pub fn variables(&self) -> usize {
+        self.table.variables()
+    }
+
+    pub fn table(&self) -> &Arc<TermTable> {
+        &self.table
+    }
+
+    /// The packed term values; bit `i % 64` of word `i / 64` is the value of term `i`, and unused
+    /// bits of the last word are zero.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn flip(&mut self, variable: usize) {
+        let cascade = self.table.cascade(variable);
+        self.words[variable / WORD_BITS] ^= 1 << (variable % WORD_BITS);
+        // tails never contain `variable`, so no bit read here is changed by this loop
+        for &(term, tail) in &cascade.terms {
+            let bit = (self.words[term / WORD_BITS] >> (term % WORD_BITS)) & 1;
+            self.words[tail / WORD_BITS] ^= bit << (tail % WORD_BITS);
+        }
+    }
+
+    pub fn get(&self, variable: usize) -> bool {
+        self.bit(self.table.variable_index(variable))
+    }
+
+    pub fn set(&mut self, variable: usize, value: bool) {
+        if self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    /// Panics if `term` is not a term of this machine.
+    pub fn get_term(&self, term: &Term) -> bool {
+        self.bit(self.table.expect_index(term))
+    }
+
+    /// Overwrites the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term of this machine.
+    pub fn set_term(&mut self, term: &Term, value: bool) {
+        let index = self.table.expect_index(term);
+        if self.bit(index) != value {
+            self.toggle(index);
+        }
+    }
+
+    /// Toggles the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term of this machine.
+    pub fn flip_term(&mut self, term: &Term) {
+        self.toggle(self.table.expect_index(term));
+    }
+
+    pub fn to_machine(&self) -> Machine {
+        let values = (0..self.table.len()).map(|index| self.bit(index)).collect();
+        Machine::from_parts(self.table.clone(), values, PropagationStrategy::default())
+    }
+
+    pub(crate) fn bit(&self, index: usize) -> bool {
+        (self.words[index / WORD_BITS] >> (index % WORD_BITS)) & 1 == 1
+    }
+
+    fn toggle(&mut self, index: usize) {
+        self.words[index / WORD_BITS] ^= 1 << (index % WORD_BITS);
+    }
+}
+
+impl From<&Machine> for PackedMachine {
+    fn from(machine: &Machine) -> Self {
+        let mut words = vec![0; machine.values().len().div_ceil(WORD_BITS)];
+        for (index, &value) in machine.values().iter().enumerate() {
+            words[index / WORD_BITS] |= (value as u64) << (index % WORD_BITS);
+        }
+        Self {
+            table: machine.table().clone(),
+            words,
+        }
+    }
+}
+
+impl DifferentialSystem for PackedMachine {
+    type Value = bool;
+
+    fn variables(&self) -> usize {
+        self.variables()
+    }
+
+    fn flip(&mut self, variable: usize) {
+        self.flip(variable);
+    }
+
+    fn get(&self, variable: usize) -> bool {
+        self.get(variable)
+    }
+
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, bool)> + '_ {
+        (0..self.table.len())
+            .filter(|&index| self.bit(index))
+            .map(|index| (self.table.term(index).clone(), true))
+    }
+}
+
+impl Debug for PackedMachine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.to_machine().fmt(f)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use crate::{Machine, PackedMachine, Term};
+
+    #[test]
+    fn matches_dense() {
+        for machine in Machine::all(3) {
+            let packed = PackedMachine::from(&machine);
+            assert_eq!(packed.words().len(), 1);
+            assert_eq!(packed.to_machine(), machine);
+            for variable in 0..3 {
+                let mut dense = machine.clone();
+                let mut packed = packed.clone();
+                dense.flip(variable);
+                packed.flip(variable);
+                assert_eq!(packed.to_machine(), dense);
+                dense.set(1, false);
+                packed.set(1, false);
+                assert_eq!(packed.to_machine(), dense);
+            }
+        }
+    }
+
+    #[test]
+    fn bounded() {
+        let mut dense = Machine::new_bounded(6, 2, |term| matches!(term, [0, 5] | [5]));
+        let mut packed = PackedMachine::from(&dense);
+        assert_eq!(packed.words().len(), 1);
+        dense.flip(0);
+        packed.flip(0);
+        assert_eq!(packed.to_machine(), dense);
+    }
+
+    #[test]
+    fn spans_words() {
+        let mut system = PackedMachine::new(5, |term| term == [4, 0, 1, 2, 3]);
+        assert_eq!(system.words().len(), 6);
+        system.flip(4);
+        assert_eq!(system.get(4), true);
+        assert_eq!(system.get_term(&Term::new([0, 1, 2, 3])), true);
+        system.set_term(&Term::new([2, 3]), true);
+        system.flip_term(&Term::new([1]));
+        let mut dense = Machine::new(5, |term| term == [4, 0, 1, 2, 3]);
+        dense.flip(4);
+        dense.set_term(&Term::new([2, 3]), true);
+        dense.flip_term(&Term::new([1]));
+        assert_eq!(system.to_machine(), dense);
+    }
+}
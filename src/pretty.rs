@@ -0,0 +1,141 @@
+//! A layout of `bool` machines for reading rather than parsing: one block per order, with a row of
+//! terms and, below it, a row of their values as `+` and `-`, aligned in columns.
+//!
+//! ```text
+//! order 1 0 1 2
+//!         + - -
+//! order 2 01 02 10 12 20 21
+//!         -  +  -  -  -  -
+//! ```
+
+use crate::{Machine, Term};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use itertools::Itertools;
+
+/// A machine's pretty layout, created by `Machine::pretty` and written with `Display`.
+#[derive(Clone, Debug)]
+pub struct Pretty<'a> {
+    machine: &'a Machine,
+    only_true: bool,
+    names: Option<Vec<String>>,
+}
+
+impl<'a> Pretty<'a> {
+    pub(crate) fn new(machine: &'a Machine) -> Self {
+        Self {
+            machine,
+            only_true: false,
+            names: None,
+        }
+    }
+
+    /// Leaves out the false terms, and with them the value rows and the orders without true terms.
+    pub fn only_true(mut self) -> Self {
+        self.only_true = true;
+        self
+    }
+
+    /// Writes variables by name instead of by index, separating the names in a term with `.`.
+    ///
+    /// Panics if the number of names differs from the number of variables.
+    pub fn names<S: ToString>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        let names = names
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names.len(),
+            self.machine.variables(),
+            "there must be one name per variable"
+        );
+        self.names = Some(names);
+        self
+    }
+
+    fn term(&self, term: &Term) -> String {
+        match &self.names {
+            Some(names) => term.iter().map(|&variable| &names[variable]).join("."),
+            None if self.machine.variables() > 10 => term.iter().join("."),
+            None => term.iter().join(""),
+        }
+    }
+}
+
+impl Display for Pretty<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        // the widest label of the table, so that every block is aligned even with false high orders
+        let label_width =
+            "order ".len() + self.machine.table().max_order().max(1).to_string().len();
+        let mut lines = Vec::new();
+        for (order, terms) in &self
+            .machine
+            .terms()
+            .filter(|&(_, value)| value || !self.only_true)
+            .chunk_by(|(term, _)| term.len())
+        {
+            let (terms, values): (Vec<_>, Vec<_>) = terms
+                .map(|(term, value)| {
+                    let term = self.term(term);
+                    let width = term.chars().count();
+                    let value = format!("{:<width$}", if value { '+' } else { '-' });
+                    (term, value)
+                })
+                .unzip();
+            lines.push(format!(
+                "{:<label_width$} {}",
+                format!("order {order}"),
+                terms.join(" ")
+            ));
+            if !self.only_true {
+                lines.push(format!("{:<label_width$} {}", "", values.join(" ")));
+            }
+        }
+        let text = lines.iter().map(|line| line.trim_end()).join("\n");
+        f.write_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+
+    #[test]
+    fn layout() {
+        let machine = Machine::new(3, |term| matches!(term, [0] | [0, 2] | [1, 2, 0]));
+        assert_eq!(
+            machine.pretty().to_string(),
+            "order 1 0 1 2\n\
+             \x20       + - -\n\
+             order 2 01 02 10 12 20 21\n\
+             \x20       -  +  -  -  -  -\n\
+             order 3 012 021 102 120 201 210\n\
+             \x20       -   -   -   +   -   -"
+        );
+        assert_eq!(
+            machine.pretty().only_true().to_string(),
+            "order 1 0\norder 2 02\norder 3 120"
+        );
+        assert_eq!(
+            machine
+                .pretty()
+                .only_true()
+                .names(["clock", "reset", "q"])
+                .to_string(),
+            "order 1 clock\norder 2 clock.q\norder 3 reset.q.clock"
+        );
+        assert_eq!(Machine::new(0, |_| false).pretty().to_string(), "");
+        assert_eq!(
+            Machine::new(2, |_| false).pretty().only_true().to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_names() {
+        let _ = Machine::new(2, |_| false).pretty().names(["a"]);
+    }
+}
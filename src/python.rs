@@ -0,0 +1,203 @@
+//! Python bindings through PyO3, built with the `python` feature as the `differential_logic`
+//! extension module, e.g. by `cargo rustc --lib --release --features python --crate-type cdylib`
+//! and renaming the library to `differential_logic.so`.
+//!
+//! `PyMachine` is exported to Python as `Machine`. Terms are tuples or lists of variable indices,
+//! and machines can be constructed from a dict mapping terms to their values, as in
+//! `Machine(3, {(0, 1): True, (2,): True})`. Invalid arguments raise `ValueError`.
+
+use crate::terms::rank;
+use crate::text::ParseMachineError;
+use crate::{Machine, MachineError, Term};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+impl From<MachineError> for PyErr {
+    fn from(error: MachineError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+impl From<ParseMachineError> for PyErr {
+    fn from(error: ParseMachineError) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+#[pyclass(
+    name = "Machine",
+    module = "differential_logic",
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PyMachine {
+    machine: Machine,
+}
+
+#[pymethods]
+impl PyMachine {
+    /// A machine over `variables` variables with the given term values; terms not in `terms` are
+    /// false.
+    #[new]
+    #[pyo3(signature = (variables, terms = None))]
+    fn new(variables: usize, terms: Option<HashMap<Vec<usize>, bool>>) -> PyResult<Self> {
+        let builder = terms
+            .unwrap_or_default()
+            .into_iter()
+            .fold(Machine::builder(variables), |builder, (term, value)| {
+                builder.term(&term, value)
+            });
+        Ok(Self {
+            machine: builder.build()?,
+        })
+    }
+
+    /// Reads a machine in the text format, e.g. `"0:-, 1:+, 01:+, 10:-"`.
+    #[staticmethod]
+    fn from_text(text: &str) -> PyResult<Self> {
+        Ok(Self {
+            machine: text.parse()?,
+        })
+    }
+
+    #[getter]
+    fn variables(&self) -> usize {
+        self.machine.variables()
+    }
+
+    fn get(&self, variable: usize) -> PyResult<bool> {
+        Ok(self.machine.try_get(variable)?)
+    }
+
+    fn flip(&mut self, variable: usize) -> PyResult<()> {
+        Ok(self.machine.try_flip(variable)?)
+    }
+
+    fn set(&mut self, variable: usize, value: bool) -> PyResult<()> {
+        Ok(self.machine.try_set(variable, value)?)
+    }
+
+    /// Flips `variable` and returns the terms whose value changed, in the order in which they were
+    /// changed.
+    fn flip_traced(&mut self, variable: usize) -> PyResult<Vec<Vec<usize>>> {
+        self.machine.try_get(variable)?;
+        Ok(self.machine.flip_traced(variable))
+    }
+
+    fn get_term(&self, term: Vec<usize>) -> PyResult<bool> {
+        Ok(self.machine.get_term(&self.term(term)?))
+    }
+
+    fn set_term(&mut self, term: Vec<usize>, value: bool) -> PyResult<()> {
+        let term = self.term(term)?;
+        self.machine.set_term(&term, value);
+        Ok(())
+    }
+
+    /// The terms with their values, in term order.
+    fn terms(&self) -> Vec<(Vec<usize>, bool)> {
+        self.machine
+            .terms()
+            .map(|(term, value)| (term.variables().to_vec(), value))
+            .collect()
+    }
+
+    /// The term values in term order, e.g. for `numpy.array(machine.values())`.
+    fn values(&self) -> Vec<bool> {
+        self.machine.terms().map(|(_, value)| value).collect()
+    }
+
+    /// The values of all variables, as a bit mask with variable `i` at bit `i`.
+    fn observables(&self) -> u64 {
+        self.machine.observables()
+    }
+
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.machine.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Machine.from_text({:?})", self.machine.to_string())
+    }
+}
+
+impl PyMachine {
+    fn term(&self, term: Vec<usize>) -> Result<Term, MachineError> {
+        match rank(self.machine.variables(), &term) {
+            Some(_) => Ok(Term::from(term)),
+            None => Err(MachineError::MalformedTerm(term)),
+        }
+    }
+}
+
+impl From<Machine> for PyMachine {
+    fn from(machine: Machine) -> Self {
+        Self { machine }
+    }
+}
+
+impl From<PyMachine> for Machine {
+    fn from(machine: PyMachine) -> Self {
+        machine.machine
+    }
+}
+
+/// The flip equivalence classes of the machines over `variables` variables, as by
+/// `Machine::flip_equivalence_classes`. Only feasible for up to 3 variables.
+#[pyfunction]
+fn flip_equivalence_classes(variables: usize) -> PyResult<Vec<Vec<PyMachine>>> {
+    if variables > 3 {
+        return Err(PyValueError::new_err(format!(
+            "there are too many machines over {variables} variables to classify"
+        )));
+    }
+    Ok(Machine::flip_equivalence_classes(variables)
+        .into_iter()
+        .map(|class| class.into_iter().map(PyMachine::from).collect())
+        .collect())
+}
+
+#[pymodule]
+fn differential_logic(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyMachine>()?;
+    module.add_function(wrap_pyfunction!(flip_equivalence_classes, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+    use crate::python::{PyMachine, flip_equivalence_classes};
+    use std::collections::HashMap;
+
+    #[test]
+    fn bindings() {
+        let terms = HashMap::from([(vec![0, 1], true), (vec![2], true)]);
+        let mut machine = PyMachine::new(3, Some(terms)).unwrap();
+        assert_eq!(
+            Machine::from(machine.clone()),
+            Machine::new(3, |term| matches!(term, [0, 1] | [2]))
+        );
+        assert_eq!(machine.flip_traced(0).unwrap(), [vec![0], vec![1]]);
+        machine.set(1, false).unwrap();
+        assert!(machine.get(0).unwrap());
+        assert_eq!(machine.observables(), 0b101);
+        assert!(machine.get_term(vec![0, 1]).unwrap());
+        machine.set_term(vec![2, 1], true).unwrap();
+        assert_eq!(machine.values().len(), machine.terms().len());
+        assert_eq!(PyMachine::from_text(&machine.__str__()).unwrap(), machine);
+
+        assert!(PyMachine::new(2, Some(HashMap::from([(vec![0, 0], true)]))).is_err());
+        assert!(machine.flip(3).is_err());
+        assert!(machine.get_term(vec![1, 3]).is_err());
+        assert!(PyMachine::from_text("01:x").is_err());
+        assert_eq!(flip_equivalence_classes(2).unwrap().len(), 5);
+        assert!(flip_equivalence_classes(4).is_err());
+    }
+}
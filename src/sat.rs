@@ -0,0 +1,212 @@
+//! Search for machines that pass given scripts, by encoding the term values as SAT variables. Runs
+//! the scripts symbolically, so it reaches variable counts at which enumerating all machines, as
+//! `Machine::all` does, is out of the question.
+//!
+//! Flips add term values to each other, which is encoded with XOR gates; a `set` only flips if the
+//! variable differs from its target, which is encoded with an AND gate per cascading term.
+
+use crate::simulate::{Script, Step};
+use crate::{Machine, MachineError, TermTable};
+use std::collections::HashMap;
+use varisat::{ExtendFormula, Lit, Solver, Var};
+
+/// Finds a machine over `variables` variables on which every script in `scripts` runs without
+/// failing, or returns `None` if there is none.
+pub fn find_machine(
+    variables: usize,
+    scripts: &[Script<bool>],
+) -> Result<Option<Machine>, MachineError> {
+    Ok(find_machines(variables, scripts, 1)?.pop())
+}
+
+/// Like `find_machine`, but returns up to `limit` different machines, all of them if there are
+/// fewer, in no particular order.
+pub fn find_machines(
+    variables: usize,
+    scripts: &[Script<bool>],
+    limit: usize,
+) -> Result<Vec<Machine>, MachineError> {
+    let table = TermTable::shared(variables);
+    let mut encoder = Encoder::new(table.len());
+    for script in scripts {
+        encoder.encode(&table, script)?;
+    }
+    let mut machines = Vec::new();
+    while machines.len() < limit && encoder.solver.solve().unwrap() {
+        let model = encoder
+            .solver
+            .model()
+            .unwrap()
+            .into_iter()
+            .map(|lit| (lit.var(), lit.is_positive()))
+            .collect::<HashMap<_, _>>();
+        // unassigned term values do not matter, so they are taken to be false
+        let values = encoder
+            .terms
+            .iter()
+            .map(|var| model.get(var).copied().unwrap_or(false))
+            .collect::<Vec<_>>();
+        let blocking = encoder
+            .terms
+            .iter()
+            .zip(&values)
+            .map(|(&var, &value)| Lit::from_var(var, !value))
+            .collect::<Vec<_>>();
+        encoder.solver.add_clause(&blocking);
+        machines.push(Machine::from_values(variables, values).unwrap());
+    }
+    Ok(machines)
+}
+
+struct Encoder {
+    solver: Solver<'static>,
+    // the SAT variable of each term's initial value
+    terms: Vec<Var>,
+    truth: Lit,
+}
+
+impl Encoder {
+    fn new(terms: usize) -> Self {
+        let mut solver = Solver::new();
+        let terms = (0..terms).map(|_| solver.new_var()).collect();
+        let truth = solver.new_lit();
+        solver.add_clause(&[truth]);
+        Self {
+            solver,
+            terms,
+            truth,
+        }
+    }
+
+    fn constant(&self, value: bool) -> Lit {
+        if value { self.truth } else { !self.truth }
+    }
+
+    fn encode(&mut self, table: &TermTable, script: &Script<bool>) -> Result<(), MachineError> {
+        let mut state = self
+            .terms
+            .iter()
+            .map(|&var| var.positive())
+            .collect::<Vec<_>>();
+        let check = |variable: usize| {
+            if variable >= table.variables() {
+                return Err(MachineError::VariableOutOfRange {
+                    variable,
+                    variables: table.variables(),
+                });
+            }
+            Ok(())
+        };
+        for step in script.steps() {
+            match *step {
+                Step::Flip(variable) => {
+                    check(variable)?;
+                    state[variable] = !state[variable];
+                    for &(term, tail) in &table.cascade(variable).terms {
+                        state[tail] = self.xor(state[tail], state[term]);
+                    }
+                }
+                Step::Set(variable, value) => {
+                    check(variable)?;
+                    // whether the variable is flipped
+                    let flipped = state[variable] ^ value;
+                    state[variable] = self.constant(value);
+                    for &(term, tail) in &table.cascade(variable).terms {
+                        let delta = self.and(flipped, state[term]);
+                        state[tail] = self.xor(state[tail], delta);
+                    }
+                }
+                Step::Expect(variable, value) => {
+                    check(variable)?;
+                    self.solver.add_clause(&[state[variable] ^ !value]);
+                }
+                Step::ExpectState(ref values) => {
+                    if values.len() != table.variables() {
+                        self.solver.add_clause(&[!self.truth]);
+                    }
+                    for (variable, &value) in values.iter().enumerate().take(table.variables()) {
+                        self.solver.add_clause(&[state[variable] ^ !value]);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn xor(&mut self, a: Lit, b: Lit) -> Lit {
+        let c = self.solver.new_lit();
+        self.solver.add_clause(&[!a, !b, !c]);
+        self.solver.add_clause(&[a, b, !c]);
+        self.solver.add_clause(&[a, !b, c]);
+        self.solver.add_clause(&[!a, b, c]);
+        c
+    }
+
+    fn and(&mut self, a: Lit, b: Lit) -> Lit {
+        let c = self.solver.new_lit();
+        self.solver.add_clause(&[a, !c]);
+        self.solver.add_clause(&[b, !c]);
+        self.solver.add_clause(&[!a, !b, c]);
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sat::{find_machine, find_machines};
+    use crate::simulate::Script;
+    use crate::{Machine, MachineError};
+
+    #[test]
+    fn matches_brute_force() {
+        let scripts = [
+            Script::new()
+                .flip(0)
+                .expect(1, true)
+                .set(1, false)
+                .expect(0, true),
+            Script::new().set(1, true).expect_state([false, true]),
+        ];
+        let passing = |machine: &Machine| scripts.iter().all(|script| script.run(machine).is_ok());
+        let mut expected = Machine::all(2)
+            .into_iter()
+            .filter(passing)
+            .collect::<Vec<_>>();
+        let mut found = find_machines(2, &scripts, usize::MAX).unwrap();
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+        assert!(!found.is_empty());
+    }
+
+    #[test]
+    fn four_variables() {
+        // a machine in which setting 0 sets 3, and flipping 3 back clears 1 and 2
+        let scripts = [Script::new()
+            .expect_state([false; 4])
+            .set(0, true)
+            .expect_state([true, false, false, true])
+            .flip(2)
+            .flip(1)
+            .expect_state([true, true, true, true])
+            .flip(3)
+            .expect_state([true, false, false, false])];
+        let machine = find_machine(4, &scripts).unwrap().unwrap();
+        assert!(scripts[0].run(&machine).is_ok());
+        assert_eq!(find_machines(4, &scripts, 3).unwrap().len(), 3);
+
+        let contradiction = [Script::new()
+            .flip(2)
+            .expect(2, true)
+            .flip(2)
+            .expect(2, true)];
+        assert_eq!(find_machine(4, &contradiction), Ok(None));
+        assert_eq!(
+            find_machine(4, &[Script::new().flip(4)]),
+            Err(MachineError::VariableOutOfRange {
+                variable: 4,
+                variables: 4
+            })
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Serde support. Machines are represented by their variable count and a list of `[term, value]`
+//! pairs, so serialized machines stay valid independently of the internal term order. Terms
+//! missing from the list are zero when deserializing. Bounded machines additionally record their
+//! `max_order`, and machines with a `PropagationStrategy` other than the default their `strategy`,
+//! as `"ascending"` or `"descending"`.
+//!
+//! `Modular` values are serialized as their representative, and deserializing rejects numbers that
+//! are not below the modulus.
+//!
+//! Traces are serialized as a list of events, each with its `operation` (`"flip"` or `"set"`),
+//! the `value` set (`null` for flips), and the other fields of `TraceEvent`.
+//!
+//! A `Census` is serialized with its fields as they are, the cycles of each variable as a list of
+//! `[length, count]` pairs.
+
+use crate::analysis::{Census, ClassCensus};
+use crate::trace::{Operation, Trace, TraceEvent};
+use crate::{GenericMachine, Modular, PropagationStrategy, Value};
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl<T: Value + Serialize> Serialize for GenericMachine<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let terms = self
+            .terms()
+            .map(|(term, value)| (term.variables(), value))
+            .collect::<Vec<_>>();
+        let bounded = self.table().is_bounded();
+        let strategy = self.strategy() != PropagationStrategy::default();
+        let mut machine =
+            serializer.serialize_struct("Machine", 2 + bounded as usize + strategy as usize)?;
+        machine.serialize_field("variables", &self.variables())?;
+        if bounded {
+            machine.serialize_field("max_order", &self.table().max_order())?;
+        } else {
+            machine.skip_field("max_order")?;
+        }
+        machine.serialize_field("terms", &terms)?;
+        if strategy {
+            machine.serialize_field("strategy", &self.strategy())?;
+        } else {
+            machine.skip_field("strategy")?;
+        }
+        machine.end()
+    }
+}
+
+impl Serialize for PropagationStrategy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match self {
+            PropagationStrategy::Simultaneous => "simultaneous",
+            PropagationStrategy::Ascending => "ascending",
+            PropagationStrategy::Descending => "descending",
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for PropagationStrategy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "simultaneous" => Ok(PropagationStrategy::Simultaneous),
+            "ascending" => Ok(PropagationStrategy::Ascending),
+            "descending" => Ok(PropagationStrategy::Descending),
+            other => Err(D::Error::custom(format!(
+                "{other:?} is not a propagation strategy"
+            ))),
+        }
+    }
+}
+
+impl<const K: u8> Serialize for Modular<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.value())
+    }
+}
+
+impl<'de, const K: u8> Deserialize<'de> for Modular<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = u8::deserialize(deserializer)?;
+        if value >= K {
+            return Err(D::Error::custom(format!(
+                "{value} is not below the modulus {K}"
+            )));
+        }
+        Ok(Modular::new(value))
+    }
+}
+
+impl Serialize for Trace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.events)
+    }
+}
+
+impl Serialize for TraceEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (operation, value) = match self.operation {
+            Operation::Flip => ("flip", None),
+            Operation::Set(value) => ("set", Some(value)),
+        };
+        let changed = self
+            .changed
+            .iter()
+            .map(|term| term.variables())
+            .collect::<Vec<_>>();
+        let mut event = serializer.serialize_struct("TraceEvent", 6)?;
+        event.serialize_field("operation", operation)?;
+        event.serialize_field("variable", &self.variable)?;
+        event.serialize_field("value", &value)?;
+        event.serialize_field("before", &self.before)?;
+        event.serialize_field("after", &self.after)?;
+        event.serialize_field("changed", &changed)?;
+        event.end()
+    }
+}
+
+impl Serialize for Census {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut census = serializer.serialize_struct("Census", 2)?;
+        census.serialize_field("variables", &self.variables)?;
+        census.serialize_field("classes", &self.classes)?;
+        census.end()
+    }
+}
+
+impl Serialize for ClassCensus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut class = serializer.serialize_struct("ClassCensus", 5)?;
+        class.serialize_field("representative", &self.representative)?;
+        class.serialize_field("states", &self.states)?;
+        class.serialize_field("cycles", &self.cycles)?;
+        class.serialize_field("fixed_points", &self.fixed_points)?;
+        class.serialize_field("diameter", &self.diameter)?;
+        class.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Machine")]
+struct Representation<T> {
+    variables: usize,
+    #[serde(default)]
+    max_order: Option<usize>,
+    terms: Vec<(Vec<usize>, T)>,
+    #[serde(default)]
+    strategy: PropagationStrategy,
+}
+
+impl<'de, T: Value + Deserialize<'de>> Deserialize<'de> for GenericMachine<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let representation = Representation::<T>::deserialize(deserializer)?;
+        let builder = GenericMachine::builder(representation.variables)
+            .max_order(representation.max_order.unwrap_or(representation.variables));
+        representation
+            .terms
+            .into_iter()
+            .fold(builder, |builder, (term, value)| builder.term(&term, value))
+            .build()
+            .map(|machine| machine.with_strategy(representation.strategy))
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::census;
+    use crate::{GenericMachine, Machine, Modular, PropagationStrategy};
+
+    #[test]
+    fn round_trip() {
+        for machine in Machine::all(2) {
+            let json = serde_json::to_string(&machine).unwrap();
+            assert_eq!(serde_json::from_str::<Machine>(&json).unwrap(), machine);
+        }
+    }
+
+    #[test]
+    fn keyed_by_term() {
+        let machine = Machine::new(2, |term| term == [1, 0]);
+        assert_eq!(
+            serde_json::to_string(&machine).unwrap(),
+            r#"{"variables":2,"terms":[[[0],false],[[1],false],[[0,1],false],[[1,0],true]]}"#
+        );
+        let reordered = r#"{"variables":2,"terms":[[[1,0],true],[[0],false]]}"#;
+        assert_eq!(serde_json::from_str::<Machine>(reordered).unwrap(), machine);
+        let invalid = r#"{"variables":2,"terms":[[[0,2],true]]}"#;
+        assert!(serde_json::from_str::<Machine>(invalid).is_err());
+    }
+
+    #[test]
+    fn bounded() {
+        let machine = Machine::new_bounded(3, 1, |term| term == [2]);
+        let json = serde_json::to_string(&machine).unwrap();
+        assert_eq!(
+            json,
+            r#"{"variables":3,"max_order":1,"terms":[[[0],false],[[1],false],[[2],true]]}"#
+        );
+        assert_eq!(serde_json::from_str::<Machine>(&json).unwrap(), machine);
+        let invalid = r#"{"variables":3,"max_order":1,"terms":[[[0,2],true]]}"#;
+        assert!(serde_json::from_str::<Machine>(invalid).is_err()); // orders below 1 are clamped to 1, as by `new_bounded`
+        let zero = r#"{"variables":3,"max_order":0,"terms":[[[2],true]]}"#;
+        assert_eq!(serde_json::from_str::<Machine>(zero).unwrap(), machine);
+    }
+
+    #[test]
+    fn strategy() {
+        let machine = Machine::new(1, |_| true).with_strategy(PropagationStrategy::Descending);
+        let json = serde_json::to_string(&machine).unwrap();
+        assert_eq!(
+            json,
+            r#"{"variables":1,"terms":[[[0],true]],"strategy":"descending"}"#
+        );
+        assert_eq!(serde_json::from_str::<Machine>(&json).unwrap(), machine);
+        let invalid = r#"{"variables":1,"terms":[],"strategy":"sideways"}"#;
+        assert!(serde_json::from_str::<Machine>(invalid).is_err());
+    }
+
+    #[test]
+    fn modular() {
+        let machine = GenericMachine::new(1, |_| Modular::<3>::new(2));
+        let json = serde_json::to_string(&machine).unwrap();
+        assert_eq!(json, r#"{"variables":1,"terms":[[[0],2]]}"#);
+        assert_eq!(
+            serde_json::from_str::<GenericMachine<Modular<3>>>(&json).unwrap(),
+            machine
+        );
+        let invalid = r#"{"variables":1,"terms":[[[0],3]]}"#;
+        assert!(serde_json::from_str::<GenericMachine<Modular<3>>>(invalid).is_err());
+    }
+
+    #[test]
+    fn trace() {
+        let mut system = Machine::new(2, |term| term == [0, 1]).into_traced();
+        system.flip(0);
+        system.set(1, true);
+        assert_eq!(
+            serde_json::to_string(system.trace()).unwrap(),
+            r#"[{"operation":"flip","variable":0,"value":null,"before":0,"after":3,"changed":[[0],[1]]},"#
+                .to_owned()
+                + r#"{"operation":"set","variable":1,"value":true,"before":3,"after":3,"changed":[]}]"#
+        );
+    }
+
+    #[test]
+    fn census_report() {
+        assert_eq!(
+            serde_json::to_string(&census(1)).unwrap(),
+            r#"{"variables":1,"classes":[{"representative":{"variables":1,"terms":[[[0],false]]},"#
+                .to_owned()
+                + r#""states":2,"cycles":[[[2,1]]],"fixed_points":0,"diameter":1}]}"#
+        );
+    }
+}
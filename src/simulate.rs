@@ -0,0 +1,276 @@
+//! Scripted runs: a `Script` of flips, sets and expectations that is run against a machine,
+//! recording every intermediate state.
+
+use crate::MachineError;
+use crate::system::DifferentialSystem;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Step<T> {
+    /// Sets a variable as by `set`.
+    Set(usize, T),
+    Flip(usize),
+    /// Expects a variable to have the given value.
+    Expect(usize, T),
+    /// Expects the variables, in order, to have the given values.
+    ExpectState(Vec<T>),
+}
+
+/// A sequence of steps, built by chaining or from a list of steps.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Script<T> {
+    steps: Vec<Step<T>>,
+}
+
+impl<T> Default for Script<T> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<T> From<Vec<Step<T>>> for Script<T> {
+    fn from(steps: Vec<Step<T>>) -> Self {
+        Self { steps }
+    }
+}
+
+impl<T> Script<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(mut self, step: Step<T>) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn set(self, variable: usize, value: T) -> Self {
+        self.step(Step::Set(variable, value))
+    }
+
+    pub fn flip(self, variable: usize) -> Self {
+        self.step(Step::Flip(variable))
+    }
+
+    pub fn expect(self, variable: usize, value: T) -> Self {
+        self.step(Step::Expect(variable, value))
+    }
+
+    pub fn expect_state(self, values: impl Into<Vec<T>>) -> Self {
+        self.step(Step::ExpectState(values.into()))
+    }
+
+    pub fn steps(&self) -> &[Step<T>] {
+        &self.steps
+    }
+
+    /// Runs the steps, in order, on a clone of `system`. Stops at the first step that refers to a
+    /// variable out of range or whose expectation does not hold.
+    pub fn run<S>(&self, system: &S) -> Result<Trace<S>, SimulationError<S>>
+    where
+        S: DifferentialSystem<Value = T>,
+        T: Copy + PartialEq,
+    {
+        let mut trace = Trace {
+            states: vec![system.clone()],
+        };
+        for (index, step) in self.steps.iter().enumerate() {
+            let mut state = trace.last().clone();
+            if let Err(failure) = apply(&mut state, step) {
+                return Err(SimulationError {
+                    step: index,
+                    failure,
+                    trace,
+                });
+            }
+            trace.states.push(state);
+        }
+        Ok(trace)
+    }
+}
+
+fn apply<S: DifferentialSystem>(
+    system: &mut S,
+    step: &Step<S::Value>,
+) -> Result<(), Failure<S::Value>> {
+    let check = |variable: usize| {
+        if variable >= system.variables() {
+            return Err(Failure::Machine(MachineError::VariableOutOfRange {
+                variable,
+                variables: system.variables(),
+            }));
+        }
+        Ok(())
+    };
+    match *step {
+        Step::Set(variable, value) => {
+            check(variable)?;
+            system.set(variable, value);
+        }
+        Step::Flip(variable) => {
+            check(variable)?;
+            system.flip(variable);
+        }
+        Step::Expect(variable, expected) => {
+            check(variable)?;
+            let actual = system.get(variable);
+            if actual != expected {
+                return Err(Failure::UnexpectedValue {
+                    variable,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Step::ExpectState(ref expected) => {
+            let actual = (0..system.variables())
+                .map(|variable| system.get(variable))
+                .collect::<Vec<_>>();
+            if actual != *expected {
+                return Err(Failure::UnexpectedState {
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The states a run went through: the initial state, followed by the state after each step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace<S> {
+    pub states: Vec<S>,
+}
+
+impl<S> Trace<S> {
+    pub fn initial(&self) -> &S {
+        &self.states[0]
+    }
+
+    pub fn last(&self) -> &S {
+        self.states.last().unwrap()
+    }
+}
+
+/// Why a step failed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Failure<T> {
+    UnexpectedValue {
+        variable: usize,
+        expected: T,
+        actual: T,
+    },
+    UnexpectedState {
+        expected: Vec<T>,
+        actual: Vec<T>,
+    },
+    Machine(MachineError),
+}
+
+/// The error returned by `Script::run`, with the states up to, but not including, the failed step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimulationError<S: DifferentialSystem> {
+    /// The index of the failed step.
+    pub step: usize,
+    pub failure: Failure<S::Value>,
+    pub trace: Trace<S>,
+}
+
+impl<S: DifferentialSystem> Display for SimulationError<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "step {}: ", self.step)?;
+        match &self.failure {
+            Failure::UnexpectedValue {
+                variable,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "expected variable {variable} to be {expected:?}, found {actual:?}"
+                )
+            }
+            Failure::UnexpectedState { expected, actual } => {
+                write!(f, "expected variables {expected:?}, found {actual:?}")
+            }
+            Failure::Machine(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl<S: DifferentialSystem + Debug> Error for SimulationError<S> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulate::{Failure, Script, Step};
+    use crate::{Machine, MachineError, SparseMachine};
+
+    #[test]
+    fn passing() {
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        let script = Script::new()
+            .flip(0)
+            .expect(0, true)
+            .expect(1, true)
+            .set(1, false)
+            .expect_state([true, false, true])
+            .flip(1)
+            .expect_state([true, true, false]);
+        let trace = script.run(&system).unwrap();
+        assert_eq!(trace.states.len(), script.steps().len() + 1);
+        assert_eq!(trace.initial(), &system);
+        let mut expected = system.clone();
+        expected.flip(0);
+        expected.set(1, false);
+        expected.flip(1);
+        assert_eq!(trace.last(), &expected);
+
+        // the same script runs on any backend
+        let sparse = script.run(&SparseMachine::from(&system)).unwrap();
+        assert_eq!(sparse.last().to_machine(), expected);
+    }
+
+    #[test]
+    fn failing() {
+        let system = Machine::new(2, |term| term == [0, 1]);
+        let error = Script::from(vec![Step::Flip(0), Step::Expect(1, false)])
+            .run(&system)
+            .unwrap_err();
+        assert_eq!(error.step, 1);
+        assert_eq!(
+            error.failure,
+            Failure::UnexpectedValue {
+                variable: 1,
+                expected: false,
+                actual: true
+            }
+        );
+        assert_eq!(error.trace.states.len(), 2);
+        assert_eq!(
+            error.to_string(),
+            "step 1: expected variable 1 to be false, found true"
+        );
+
+        let error = Script::new()
+            .expect_state([false])
+            .run(&system)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "step 0: expected variables [false], found [false, false]"
+        );
+
+        let error = Script::new().flip(0).set(2, true).run(&system).unwrap_err();
+        assert_eq!(
+            error.failure,
+            Failure::Machine(MachineError::VariableOutOfRange {
+                variable: 2,
+                variables: 2
+            })
+        );
+    }
+}
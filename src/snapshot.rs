@@ -0,0 +1,26 @@
+use crate::PackedMachine;
+use core::fmt::{Debug, Formatter};
+
+/// A bit-packed copy of a `bool` machine's values, taken by `Machine::snapshot` and put back by
+/// `Machine::restore`.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct Snapshot {
+    pub(crate) packed: PackedMachine,
+}
+
+impl Snapshot {
+    pub fn variables(&self) -> usize {
+        self.packed.variables()
+    }
+
+    /// The packed values, in the layout of `PackedMachine::words`.
+    pub fn words(&self) -> &[u64] {
+        self.packed.words()
+    }
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Snapshot").field(&self.packed).finish()
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{DifferentialSystem, Machine, MachineError, Term};
+use alloc::collections::BTreeSet;
+use core::fmt::{Debug, Formatter};
+use itertools::Itertools;
+
+/// A `bool` machine that only stores its true terms. It behaves exactly like the corresponding
+/// `Machine`, but flipping and querying never touch the factorially large table of all terms, so
+/// machines with many variables and few true terms stay small. Build those with
+/// `from_true_terms`; `new` and `to_machine` visit every term.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SparseMachine {
+    variables: usize,
+    true_terms: BTreeSet<Term>,
+}
+
+impl SparseMachine {
+    /// Calls `initial_values_producer` with every term, in term order, so this takes time
+    /// factorial in `variables`; use `from_true_terms` for many variables. Unlike `Machine::new`,
+    /// it does not build or cache a term table.
+    pub fn new(
+        variables: usize,
+        mut initial_values_producer: impl FnMut(&[usize]) -> bool,
+    ) -> Self {
+        let true_terms = (1..=variables)
+            .flat_map(|len| (0..variables).permutations(len))
+            .filter(|term| initial_values_producer(term))
+            .map(Term::new)
+            .collect();
+        Self {
+            variables,
+            true_terms,
+        }
+    }
+
+    /// Creates a machine in which exactly the given terms are true.
+    pub fn from_true_terms(
+        variables: usize,
+        true_terms: impl IntoIterator<Item = Term>,
+    ) -> Result<Self, MachineError> {
+        let mut machine = Self {
+            variables,
+            true_terms: BTreeSet::new(),
+        };
+        for term in true_terms {
+            machine.check_term(&term)?;
+            machine.true_terms.insert(term);
+        }
+        Ok(machine)
+    }
+
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// Iterates over the true terms in lexicographic order.
+    pub fn true_terms(&self) -> impl Iterator<Item = &Term> {
+        self.true_terms.iter()
+    }
+
+    pub fn flip(&mut self, variable: usize) {
+        self.check_variable(variable);
+        self.toggle(Term::new([variable]));
+        // every term starting with `variable` lies in this range, and only these are read
+        let tails = self
+            .true_terms
+            .range(Term::new([variable])..Term::new([variable + 1]))
+            .filter(|term| term.len() > 1)
+            .map(|term| Term::from(&term[1..]))
+            .collect_vec();
+        for tail in tails {
+            self.toggle(tail);
+        }
+    }
+
+    pub fn get(&self, variable: usize) -> bool {
+        self.check_variable(variable);
+        self.true_terms.contains(&Term::new([variable]))
+    }
+
+    pub fn set(&mut self, variable: usize, value: bool) {
+        if self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    pub fn get_term(&self, term: &Term) -> bool {
+        self.true_terms.contains(term)
+    }
+
+    /// Overwrites the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term over this machine's variables.
+    pub fn set_term(&mut self, term: &Term, value: bool) {
+        self.expect_term(term);
+        if value {
+            self.true_terms.insert(term.clone());
+        } else {
+            self.true_terms.remove(term);
+        }
+    }
+
+    /// Toggles the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a term over this machine's variables.
+    pub fn flip_term(&mut self, term: &Term) {
+        self.expect_term(term);
+        self.toggle(term.clone());
+    }
+
+    // toggles a term known to be valid
+    fn toggle(&mut self, term: Term) {
+        if !self.true_terms.remove(&term) {
+            self.true_terms.insert(term);
+        }
+    }
+
+    pub fn to_machine(&self) -> Machine {
+        Machine::new(self.variables, |term| self.true_terms.contains(term))
+    }
+
+    fn check_variable(&self, variable: usize) {
+        assert!(
+            variable < self.variables,
+            "variable {variable} is out of range for a machine with {} variables",
+            self.variables
+        );
+    }
+
+    fn expect_term(&self, term: &Term) {
+        if let Err(error) = self.check_term(term) {
+            panic!("{error}");
+        }
+    }
+
+    fn check_term(&self, term: &Term) -> Result<(), MachineError> {
+        if let Some(&variable) = term.iter().find(|&&variable| variable >= self.variables) {
+            return Err(MachineError::VariableOutOfRange {
+                variable,
+                variables: self.variables,
+            });
+        }
+        if term.is_empty() || !crate::all_distinct(term.iter()) {
+            return Err(MachineError::MalformedTerm(term.to_vec()));
+        }
+        Ok(())
+    }
+}
+
+impl From<&Machine> for SparseMachine {
+    fn from(machine: &Machine) -> Self {
+        Self {
+            variables: machine.variables(),
+            true_terms: machine
+                .terms()
+                .filter(|&(_, value)| value)
+                .map(|(term, _)| term.clone())
+                .collect(),
+        }
+    }
+}
+
+impl DifferentialSystem for SparseMachine {
+    type Value = bool;
+
+    fn variables(&self) -> usize {
+        self.variables
+    }
+
+    fn flip(&mut self, variable: usize) {
+        self.flip(variable);
+    }
+
+    fn get(&self, variable: usize) -> bool {
+        self.get(variable)
+    }
+
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, bool)> + '_ {
+        self.true_terms.iter().map(|term| (term.clone(), true))
+    }
+}
+
+impl Debug for SparseMachine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_set().entries(&self.true_terms).finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use crate::{Machine, MachineError, SparseMachine, Term};
+
+    #[test]
+    fn matches_dense() {
+        for machine in Machine::all(3) {
+            for variable in 0..3 {
+                let mut dense = machine.clone();
+                let mut sparse = SparseMachine::from(&machine);
+                dense.flip(variable);
+                sparse.flip(variable);
+                assert_eq!(sparse.to_machine(), dense);
+                dense.set(2, true);
+                sparse.set(2, true);
+                assert_eq!(sparse.to_machine(), dense);
+            }
+        }
+    }
+
+    #[test]
+    fn many_variables() {
+        let mut system = SparseMachine::from_true_terms(
+            20,
+            [
+                Term::new([3, 17]),
+                Term::new([17, 3]),
+                Term::new([3, 5, 19]),
+            ],
+        )
+        .unwrap();
+        system.set(3, true);
+        assert_eq!(system.get(3), true);
+        assert_eq!(system.get(17), true);
+        assert_eq!(system.get_term(&Term::new([5, 19])), true);
+        system.set(17, false);
+        assert_eq!(system.get(3), false);
+        assert_eq!(system.true_terms().count(), 4);
+    }
+
+    #[test]
+    fn invalid_terms() {
+        assert_eq!(
+            SparseMachine::from_true_terms(2, [Term::new([0, 0])]),
+            Err(MachineError::MalformedTerm(vec![0, 0]))
+        );
+        assert_eq!(
+            SparseMachine::from_true_terms(2, [Term::new([2])]),
+            Err(MachineError::VariableOutOfRange {
+                variable: 2,
+                variables: 2
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "term [0, 0] is not a non-empty sequence of distinct variables")]
+    fn set_malformed_term() {
+        SparseMachine::new(2, |_| false).set_term(&Term::new([0, 0]), true);
+    }
+
+    #[test]
+    #[should_panic(expected = "variable 2 is out of range for a machine with 2 variables")]
+    fn flip_term_out_of_range() {
+        SparseMachine::new(2, |_| false).flip_term(&Term::new([1, 2]));
+    }
+
+    #[test]
+    fn new_visits_every_term() {
+        let mut visited = Vec::new();
+        let system = SparseMachine::new(3, |term| {
+            visited.push(term.to_vec());
+            term == [2, 0, 1]
+        });
+        let machine = Machine::new(3, |term| term == [2, 0, 1]);
+        assert_eq!(system.to_machine(), machine);
+        let terms = machine
+            .terms()
+            .map(|(term, _)| term.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(visited, terms);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::Machine;
+use crate::system::{self, DifferentialSystem};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// The directed graph of machine states, with an edge labeled `v` from each state to the state
+/// that flipping variable `v` leads to. The states can be of any `DifferentialSystem`; the KISS2
+/// export needs `bool` values.
+#[derive(Clone, Debug)]
+pub struct StateGraph<S: DifferentialSystem = Machine> {
+    states: Vec<S>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub variable: usize,
+}
+
+impl<S: DifferentialSystem> StateGraph<S> {
+    /// The graph of all states reachable from `start`, which is state `0`.
+    pub fn from_machine(start: &S) -> Self {
+        Self::from_states(system::reachable(start))
+    }
+
+    fn from_states(states: Vec<S>) -> Self {
+        let indices = states
+            .iter()
+            .enumerate()
+            .map(|(index, state)| (state, index))
+            .collect::<HashMap<_, _>>();
+        let mut edges = Vec::new();
+        for (from, state) in states.iter().enumerate() {
+            for variable in 0..state.variables() {
+                let mut next = state.clone();
+                next.flip(variable);
+                edges.push(Edge {
+                    from,
+                    to: indices[&next],
+                    variable,
+                });
+            }
+        }
+        Self { states, edges }
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Writes the graph in Graphviz DOT format. Nodes are labeled with their index and the values
+    /// of the variables.
+    pub fn write_dot(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "digraph states {{")?;
+        for (index, state) in self.states.iter().enumerate() {
+            writeln!(
+                out,
+                "    s{index} [label=\"{index}: {}\"];",
+                Self::observables(state)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                out,
+                "    s{} -> s{} [label=\"{}\"];",
+                edge.from, edge.to, edge.variable
+            )?;
+        }
+        writeln!(out, "}}")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = Vec::new();
+        self.write_dot(&mut dot).unwrap();
+        String::from_utf8(dot).unwrap()
+    }
+
+    /// Writes the graph in GraphML format, with the variable values as the `observables` node
+    /// attribute and the flipped variable as the `variable` edge attribute.
+    pub fn write_graphml(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            out,
+            r#"  <key id="observables" for="node" attr.name="observables" attr.type="string"/>"#
+        )?;
+        writeln!(
+            out,
+            r#"  <key id="variable" for="edge" attr.name="variable" attr.type="int"/>"#
+        )?;
+        writeln!(out, r#"  <graph id="states" edgedefault="directed">"#)?;
+        for (index, state) in self.states.iter().enumerate() {
+            writeln!(
+                out,
+                r#"    <node id="s{index}"><data key="observables">{}</data></node>"#,
+                Self::observables(state)
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                out,
+                r#"    <edge source="s{}" target="s{}"><data key="variable">{}</data></edge>"#,
+                edge.from, edge.to, edge.variable
+            )?;
+        }
+        writeln!(out, "  </graph>")?;
+        writeln!(out, "</graphml>")
+    }
+
+    /// Converts the graph into a `petgraph` graph with the same node and edge indices, weighted with
+    /// the states and the flipped variables.
+    #[cfg(feature = "petgraph")]
+    pub fn to_petgraph(&self) -> petgraph::Graph<S, usize> {
+        use petgraph::graph::NodeIndex;
+
+        let mut graph = petgraph::Graph::<_, _>::with_capacity(self.states.len(), self.edges.len());
+        for state in &self.states {
+            graph.add_node(state.clone());
+        }
+        for edge in &self.edges {
+            graph.add_edge(
+                NodeIndex::new(edge.from),
+                NodeIndex::new(edge.to),
+                edge.variable,
+            );
+        }
+        graph
+    }
+
+    fn observables(state: &S) -> String {
+        (0..state.variables())
+            .map(|variable| format!("{:?}", state.get(variable)))
+            .join(" ")
+    }
+}
+
+impl StateGraph<Machine> {
+    /// The graph of all machines over `variables` variables, in the order of `Machine::all`.
+    pub fn all(variables: usize) -> Self {
+        Self::from_states(Machine::all(variables))
+    }
+}
+
+impl<S: DifferentialSystem<Value = bool>> StateGraph<S> {
+    /// Writes the graph as a Mealy machine in the KISS2 format, with state `0` as the reset state.
+    /// Each transition's input has a `1` for the flipped variable and a `0` for every other one,
+    /// and its output gives the values of the variables in the state it leads to, both with
+    /// variable `0` first.
+    pub fn write_kiss2(&self, out: &mut impl Write) -> io::Result<()> {
+        let variables = self.states.first().map_or(0, S::variables);
+        writeln!(out, ".i {variables}")?;
+        writeln!(out, ".o {variables}")?;
+        writeln!(out, ".p {}", self.edges.len())?;
+        writeln!(out, ".s {}", self.states.len())?;
+        writeln!(out, ".r s0")?;
+        for edge in &self.edges {
+            let input = (0..variables)
+                .map(|variable| if variable == edge.variable { '1' } else { '0' })
+                .collect::<String>();
+            let output = (0..variables)
+                .map(|variable| {
+                    if self.states[edge.to].get(variable) {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect::<String>();
+            writeln!(out, "{input} s{} s{} {output}", edge.from, edge.to)?;
+        }
+        writeln!(out, ".e")
+    }
+
+    pub fn to_kiss2(&self) -> String {
+        let mut kiss2 = Vec::new();
+        self.write_kiss2(&mut kiss2).unwrap();
+        String::from_utf8(kiss2).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state_graph::{Edge, StateGraph};
+    use crate::{Machine, PackedMachine, SparseMachine};
+
+    #[test]
+    fn from_machine() {
+        let system = Machine::new(2, |term| term == [0, 1]);
+        let graph = StateGraph::from_machine(&system);
+        assert_eq!(graph.states().len(), 4);
+        assert_eq!(graph.edges().len(), 8);
+        assert_eq!(graph.states()[0], system);
+        for edge in graph.edges() {
+            let mut next = graph.states()[edge.from].clone();
+            next.flip(edge.variable);
+            assert_eq!(graph.states()[edge.to], next);
+        }
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph states {\n"));
+        assert!(dot.contains("    s0 [label=\"0: false false\"];\n"));
+        let Edge { from, to, variable } = graph.edges()[0];
+        assert!(dot.contains(&format!("    s{from} -> s{to} [label=\"{variable}\"];\n")));
+    }
+
+    #[test]
+    fn other_backends() {
+        let system = Machine::new(3, |term| matches!(term, [0, 1] | [2, 1, 0]));
+        let dense = StateGraph::from_machine(&system);
+        let sparse = StateGraph::from_machine(&SparseMachine::from(&system));
+        let packed = StateGraph::from_machine(&PackedMachine::from(&system));
+        assert_eq!(sparse.edges(), dense.edges());
+        assert_eq!(packed.edges(), dense.edges());
+        assert_eq!(packed.to_dot(), dense.to_dot());
+        assert_eq!(sparse.to_kiss2(), dense.to_kiss2());
+        assert!(
+            (sparse.states().iter())
+                .zip(dense.states())
+                .all(|(sparse, dense)| sparse.to_machine() == *dense)
+        );
+    }
+
+    #[test]
+    fn all() {
+        let graph = StateGraph::all(2);
+        assert_eq!(graph.states().len(), 16);
+        assert_eq!(graph.edges().len(), 32);
+        let mut graphml = Vec::new();
+        graph.write_graphml(&mut graphml).unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert_eq!(graphml.matches("<node ").count(), 16);
+        assert_eq!(graphml.matches("<edge ").count(), 32);
+        assert!(graphml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn kiss2() {
+        let system = Machine::new(2, |term| term == [0, 1]);
+        let fsm = system.to_fsm();
+        assert_eq!(fsm.states().len(), 4);
+        let kiss2 = fsm.to_kiss2();
+        assert!(kiss2.starts_with(".i 2\n.o 2\n.p 8\n.s 4\n.r s0\n"));
+        // flipping 0 in the initial state sets both variables
+        assert!(kiss2.contains("\n10 s0 s1 11\n"));
+        assert!(kiss2.ends_with(".e\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "petgraph")]
+    fn petgraph() {
+        use petgraph::graph::{EdgeIndex, NodeIndex};
+
+        let graph = StateGraph::from_machine(&Machine::new(2, |term| term == [0, 1]));
+        let converted = graph.to_petgraph();
+        assert_eq!(converted.node_count(), 4);
+        assert_eq!(converted.edge_count(), 8);
+        assert_eq!(
+            converted[petgraph::graph::NodeIndex::new(0)],
+            graph.states()[0]
+        );
+        for (index, edge) in graph.edges().iter().enumerate() {
+            let index = EdgeIndex::new(index);
+            assert_eq!(converted[index], edge.variable);
+            assert_eq!(
+                converted.edge_endpoints(index),
+                Some((NodeIndex::new(edge.from), NodeIndex::new(edge.to)))
+            );
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{DifferentialSystem, GenericMachine, MachineError, Term, Value};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+/// A machine whose terms are the non-empty sets of variables rather than their orderings, so that
+/// it has `2^n - 1` terms instead of one per partial permutation. Flipping `v` adds `ONE` to `v`
+/// and then the value of every set `{v} ∪ rest` to the set `rest`.
+///
+/// It behaves exactly like the `GenericMachine` in which every ordering of a set has the set's
+/// value, which `to_machine` returns; conversely, symmetric machines convert to subset machines.
+///
+/// Terms are returned with their variables in ascending order and accepted in any order. Sets are
+/// stored by their bit masks, so there are fewer than `usize::BITS` variables.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SubsetMachine<T: Value = bool> {
+    variables: usize,
+    // indexed by the bit mask of the set; index 0, the empty set, is always zero
+    values: Vec<T>,
+}
+
+impl<T: Value> SubsetMachine<T> {
+    /// Calls `initial_values_producer` with the variables of every set, in ascending order.
+    ///
+    /// Panics if `variables >= usize::BITS`.
+    pub fn new(variables: usize, mut initial_values_producer: impl FnMut(&[usize]) -> T) -> Self {
+        assert!(
+            variables < usize::BITS as usize,
+            "subset machines have fewer than {} variables",
+            usize::BITS
+        );
+        let values = (0..1usize << variables)
+            .map(|mask| match mask {
+                0 => T::ZERO,
+                _ => initial_values_producer(&set(mask)),
+            })
+            .collect();
+        Self { variables, values }
+    }
+
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// The number of terms, `2^variables - 1`.
+    pub fn len(&self) -> usize {
+        self.values.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics if `variable >= self.variables()`.
+    pub fn flip(&mut self, variable: usize) {
+        self.check_variable(variable);
+        let bit = 1 << variable;
+        self.values[bit] = self.values[bit].add(T::ONE);
+        // the sets read contain `variable` and the sets written do not, so the order is irrelevant
+        for rest in 1..self.values.len() {
+            if rest & bit == 0 {
+                let delta = self.values[rest | bit];
+                if delta != T::ZERO {
+                    self.values[rest] = self.values[rest].add(delta);
+                }
+            }
+        }
+    }
+
+    /// Panics if `variable >= self.variables()`.
+    pub fn get(&self, variable: usize) -> T {
+        self.check_variable(variable);
+        self.values[1 << variable]
+    }
+
+    /// Flips `variable` until it has the given value.
+    ///
+    /// Panics if `variable >= self.variables()`.
+    pub fn set(&mut self, variable: usize, value: T) {
+        while self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    /// Panics if `term` is not a set of this machine's variables.
+    pub fn get_term(&self, term: &Term) -> T {
+        self.values[self.expect_mask(term)]
+    }
+
+    /// Overwrites the value of a single term, without any cascade.
+    ///
+    /// Panics if `term` is not a set of this machine's variables.
+    pub fn set_term(&mut self, term: &Term, value: T) {
+        let mask = self.expect_mask(term);
+        self.values[mask] = value;
+    }
+
+    /// Iterates over all terms and their values, in the order of their bit masks.
+    pub fn terms(&self) -> impl Iterator<Item = (Term, T)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(mask, &value)| (Term::new(set(mask)), value))
+    }
+
+    /// The machine over ordered terms in which every ordering of a set has the set's value.
+    pub fn to_machine(&self) -> GenericMachine<T> {
+        GenericMachine::new(self.variables, |term| self.values[self.expect_mask(term)])
+    }
+
+    fn check_variable(&self, variable: usize) {
+        assert!(
+            variable < self.variables,
+            "variable {variable} is out of range for a machine with {} variables",
+            self.variables
+        );
+    }
+
+    fn expect_mask(&self, term: &[usize]) -> usize {
+        let mut mask = 0usize;
+        for &variable in term {
+            assert!(
+                variable < self.variables && mask & 1 << variable == 0,
+                "{term:?} is not a term of this machine"
+            );
+            mask |= 1 << variable;
+        }
+        assert!(mask != 0, "{term:?} is not a term of this machine");
+        mask
+    }
+}
+
+// the variables in the set with the given bit mask, in ascending order
+fn set(mask: usize) -> Vec<usize> {
+    (0..usize::BITS as usize)
+        .filter(|&variable| mask >> variable & 1 == 1)
+        .collect()
+}
+
+/// Fails with `AsymmetricTerm` unless every term of `machine` has the same value as each
+/// reordering of its variables.
+impl<T: Value> TryFrom<&GenericMachine<T>> for SubsetMachine<T> {
+    type Error = MachineError;
+
+    fn try_from(machine: &GenericMachine<T>) -> Result<Self, Self::Error> {
+        let value = |term: &[usize]| {
+            let mut sorted = term.to_vec();
+            sorted.sort_unstable();
+            machine.get_term(&Term::new(sorted))
+        };
+        if let Some((term, _)) = machine.terms().find(|&(term, other)| value(term) != other) {
+            return Err(MachineError::AsymmetricTerm(term.to_vec()));
+        }
+        Ok(Self::new(machine.variables(), value))
+    }
+}
+
+impl<T: Value> DifferentialSystem for SubsetMachine<T> {
+    type Value = T;
+
+    fn variables(&self) -> usize {
+        self.variables
+    }
+
+    fn flip(&mut self, variable: usize) {
+        self.flip(variable);
+    }
+
+    fn get(&self, variable: usize) -> T {
+        self.get(variable)
+    }
+
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, T)> + '_ {
+        self.terms().filter(|&(_, value)| value != T::ZERO)
+    }
+}
+
+impl<T: Value> Debug for SubsetMachine<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(self.terms()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::reachable;
+    use crate::{Machine, MachineError, Modular, SubsetMachine, Term, Value};
+    use itertools::Itertools;
+
+    #[test]
+    fn matches_symmetric_machines() {
+        let sets = SubsetMachine::new(3, |_| false).len();
+        for bits in 0..1u32 << sets {
+            let mut index = 0;
+            let subsets = SubsetMachine::new(3, |_| {
+                index += 1;
+                bits >> (index - 1) & 1 == 1
+            });
+            let machine = subsets.to_machine();
+            assert!(machine.is_symmetric());
+            assert_eq!(SubsetMachine::try_from(&machine), Ok(subsets.clone()));
+            for variable in 0..3 {
+                let (mut subsets, mut machine) = (subsets.clone(), machine.clone());
+                subsets.flip(variable);
+                machine.flip(variable);
+                assert_eq!(subsets.to_machine(), machine);
+            }
+        }
+    }
+
+    #[test]
+    fn terms() {
+        let mut system = SubsetMachine::new(3, |term| term == [0, 2]);
+        assert_eq!(system.len(), 7);
+        assert!(system.get_term(&Term::new([2, 0])));
+        system.flip(2);
+        assert!(system.get(0) && system.get(2));
+        system.set_term(&Term::new([1, 0, 2]), true);
+        assert_eq!(
+            system
+                .terms()
+                .filter(|&(_, value)| value)
+                .map(|(term, _)| term)
+                .collect_vec(),
+            [
+                Term::new([0]),
+                Term::new([2]),
+                Term::new([0, 2]),
+                Term::new([0, 1, 2])
+            ]
+        );
+        assert_eq!(format!("{system:?}").matches("true").count(), 4);
+    }
+
+    #[test]
+    fn asymmetric() {
+        let machine = Machine::new(2, |term| term == [1, 0]);
+        assert_eq!(
+            SubsetMachine::try_from(&machine),
+            Err(MachineError::AsymmetricTerm(vec![1, 0]))
+        );
+    }
+
+    #[test]
+    fn many_variables() {
+        // 2^16 - 1 terms, where ordered terms would number in the tens of trillions
+        let mut system = SubsetMachine::new(16, |term| term == [3, 9, 15]);
+        system.flip(3);
+        assert!(system.get_term(&Term::new([9, 15])));
+        system.flip(9);
+        assert!(system.get(15));
+        assert!(system.get(3) && system.get(9));
+    }
+
+    #[test]
+    fn modular_values() {
+        let start = SubsetMachine::new(2, |term| match term {
+            [0, 1] => Modular::<3>::ONE,
+            _ => Modular::ZERO,
+        });
+        // both flips add one to both variables, as the set {0, 1} is one
+        assert_eq!(reachable(&start).len(), 3);
+    }
+}
@@ -0,0 +1,145 @@
+//! Construction of machines from examples of their behavior.
+
+use crate::simulate::{Script, Step};
+use crate::{Machine, MachineError};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SynthesisError {
+    /// No machine behaves as in all examples.
+    Inconsistent,
+    /// There are too many machines to search without the `varisat` feature.
+    TooManyVariables(usize),
+    Machine(MachineError),
+}
+
+impl Display for SynthesisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SynthesisError::Inconsistent => write!(f, "no machine is consistent with the examples"),
+            SynthesisError::TooManyVariables(variables) => {
+                write!(
+                    f,
+                    "searching machines over {variables} variables requires the `varisat` feature"
+                )
+            }
+            SynthesisError::Machine(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+impl Error for SynthesisError {}
+
+impl From<MachineError> for SynthesisError {
+    fn from(error: MachineError) -> Self {
+        SynthesisError::Machine(error)
+    }
+}
+
+/// Finds a machine over `variables` variables on which each example's script runs without failing
+/// and ends with the example's observables, as returned by `Machine::observables`.
+///
+/// With the `varisat` feature, this uses `sat::find_machine`; otherwise, it tries all machines in
+/// order, which is only feasible for up to 3 variables. An expected observables mask with bits
+/// beyond the machine's variables makes the examples `Inconsistent`, as no machine sets them.
+pub fn synthesize(
+    variables: usize,
+    examples: &[(Script<bool>, u64)],
+) -> Result<Machine, SynthesisError> {
+    for (script, observables) in examples {
+        check_variables(variables, script)?;
+        if variables < 64 && observables >> variables != 0 {
+            return Err(SynthesisError::Inconsistent);
+        }
+    }
+    let scripts = examples
+        .iter()
+        .map(|(script, observables)| {
+            let state = (0..variables)
+                .map(|variable| observables >> variable & 1 == 1)
+                .collect();
+            script.clone().step(Step::ExpectState(state))
+        })
+        .collect::<Vec<_>>();
+    search(variables, &scripts)?.ok_or(SynthesisError::Inconsistent)
+}
+
+fn check_variables(variables: usize, script: &Script<bool>) -> Result<(), MachineError> {
+    for step in script.steps() {
+        if let Step::Set(variable, _) | Step::Flip(variable) | Step::Expect(variable, _) = *step
+            && variable >= variables
+        {
+            return Err(MachineError::VariableOutOfRange {
+                variable,
+                variables,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "varisat")]
+fn search(variables: usize, scripts: &[Script<bool>]) -> Result<Option<Machine>, SynthesisError> {
+    Ok(crate::sat::find_machine(variables, scripts)?)
+}
+
+#[cfg(not(feature = "varisat"))]
+fn search(variables: usize, scripts: &[Script<bool>]) -> Result<Option<Machine>, SynthesisError> {
+    if variables > 3 {
+        return Err(SynthesisError::TooManyVariables(variables));
+    }
+    Ok(Machine::iter_all(variables)
+        .find(|machine| scripts.iter().all(|script| script.run(machine).is_ok())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulate::Script;
+    use crate::synthesis::SynthesisError;
+    use crate::{Machine, MachineError};
+
+    #[test]
+    fn from_examples() {
+        // setting 0 copies its value to 1, and flipping 1 leaves 0 alone
+        let examples = [
+            (Script::new().set(0, true), 0b11),
+            (Script::new().set(0, true).set(0, false), 0b00),
+            (Script::new().flip(1), 0b10),
+        ];
+        let machine = Machine::synthesize(2, &examples).unwrap();
+        for (script, observables) in &examples {
+            assert_eq!(
+                script.run(&machine).unwrap().last().observables(),
+                *observables
+            );
+        }
+
+        let contradiction = [(Script::new(), 0b00), (Script::new(), 0b01)];
+        assert_eq!(
+            Machine::synthesize(2, &contradiction),
+            Err(SynthesisError::Inconsistent)
+        );
+        assert_eq!(
+            Machine::synthesize(2, &[(Script::new(), 0b100)]),
+            Err(SynthesisError::Inconsistent)
+        );
+        assert_eq!(
+            Machine::synthesize(2, &[(Script::new().flip(2), 0b00)]),
+            Err(SynthesisError::Machine(MachineError::VariableOutOfRange {
+                variable: 2,
+                variables: 2
+            }))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "varisat"))]
+    fn too_many_variables() {
+        assert_eq!(
+            Machine::synthesize(4, &[]),
+            Err(SynthesisError::TooManyVariables(4))
+        );
+    }
+}
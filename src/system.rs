@@ -0,0 +1,107 @@
+//! A common interface over the machine representations, so that analyses can be written once.
+
+use crate::collections::HashSet;
+use crate::{GenericMachine, Term, Value};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+/// The operations every machine representation supports, with the semantics of `GenericMachine`.
+pub trait DifferentialSystem: Clone + Eq + Hash {
+    type Value: Value;
+
+    fn variables(&self) -> usize;
+
+    /// Panics if `variable >= self.variables()`.
+    fn flip(&mut self, variable: usize);
+
+    /// Panics if `variable >= self.variables()`.
+    fn get(&self, variable: usize) -> Self::Value;
+
+    /// Flips `variable` until it has the given value.
+    ///
+    /// Panics if `variable >= self.variables()`.
+    fn set(&mut self, variable: usize, value: Self::Value) {
+        while self.get(variable) != value {
+            self.flip(variable);
+        }
+    }
+
+    /// Iterates over the terms with a nonzero value, in an order that depends on the representation.
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, Self::Value)> + '_;
+}
+
+impl<T: Value> DifferentialSystem for GenericMachine<T> {
+    type Value = T;
+
+    fn variables(&self) -> usize {
+        self.variables()
+    }
+
+    fn flip(&mut self, variable: usize) {
+        self.flip(variable);
+    }
+
+    fn get(&self, variable: usize) -> T {
+        self.get(variable)
+    }
+
+    fn nonzero_terms(&self) -> impl Iterator<Item = (Term, T)> + '_ {
+        self.terms()
+            .filter(|&(_, value)| value != T::ZERO)
+            .map(|(term, value)| (term.clone(), value))
+    }
+}
+
+/// Returns every system reachable from `start` by a sequence of flips, including `start`, in
+/// breadth-first order.
+pub fn reachable<S: DifferentialSystem>(start: &S) -> Vec<S> {
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([start.clone()]);
+    let mut reachable = Vec::new();
+    while let Some(system) = queue.pop_front() {
+        for variable in 0..system.variables() {
+            let mut neighbour = system.clone();
+            neighbour.flip(variable);
+            if seen.insert(neighbour.clone()) {
+                queue.push_back(neighbour);
+            }
+        }
+        reachable.push(system);
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::system::{DifferentialSystem, reachable};
+    use crate::{FixedMachine, Machine, PackedMachine, SparseMachine};
+    use itertools::Itertools;
+
+    fn check<S: DifferentialSystem<Value = bool>>(system: S, to_machine: impl Fn(&S) -> Machine) {
+        let machine = to_machine(&system);
+        assert_eq!(
+            reachable(&system).iter().map(to_machine).collect_vec(),
+            machine.reachable()
+        );
+        assert_eq!(
+            system.nonzero_terms().sorted().collect_vec(),
+            DifferentialSystem::nonzero_terms(&machine)
+                .sorted()
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn backends_agree() {
+        for machine in Machine::all(2) {
+            check(machine.clone(), Machine::clone);
+            check(SparseMachine::from(&machine), SparseMachine::to_machine);
+            check(PackedMachine::from(&machine), PackedMachine::to_machine);
+            check(
+                FixedMachine::<2>::try_from(&machine).unwrap(),
+                FixedMachine::to_machine,
+            );
+        }
+    }
+}
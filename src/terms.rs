@@ -0,0 +1,601 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use itertools::Itertools;
+#[cfg(feature = "std")]
+use std::sync::{OnceLock, RwLock};
+
+#[cfg(feature = "std")]
+static SHARED: OnceLock<RwLock<TermTables>> = OnceLock::new();
+
+/// Builds the process-wide table for `variables` variables, if it is not cached yet, so that the
+/// first machines over that many variables do not pay for it.
+#[cfg(feature = "std")]
+pub fn precompute(variables: usize) {
+    TermTable::shared(variables);
+}
+
+/// The approximate number of bytes held by the tables in the process-wide cache, as by
+/// `TermTable::size_bytes`.
+#[cfg(feature = "std")]
+pub fn cache_size_bytes() -> usize {
+    SHARED
+        .get()
+        .map_or(0, |cache| cache.read().unwrap().size_bytes())
+}
+
+/// Empties the process-wide cache. Tables still used by machines stay alive until those machines
+/// are dropped, but later calls to `TermTable::shared` build new tables.
+#[cfg(feature = "std")]
+pub fn clear() {
+    if let Some(cache) = SHARED.get() {
+        cache.write().unwrap().clear();
+    }
+}
+
+/// A non-empty sequence of distinct variables, identifying one of a machine's values.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Term(Vec<usize>);
+
+impl Term {
+    pub fn new(variables: impl Into<Vec<usize>>) -> Self {
+        Self(variables.into())
+    }
+
+    pub fn variables(&self) -> &[usize] {
+        &self.0
+    }
+
+    pub fn into_variables(self) -> Vec<usize> {
+        self.0
+    }
+}
+
+impl Deref for Term {
+    type Target = [usize];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Borrow<[usize]> for Term {
+    fn borrow(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for Term {
+    fn from(variables: Vec<usize>) -> Self {
+        Self(variables)
+    }
+}
+
+impl From<&[usize]> for Term {
+    fn from(variables: &[usize]) -> Self {
+        Self(variables.to_vec())
+    }
+}
+
+impl Debug for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The index of `term` among the terms over `variables` variables, in `TermTable` order, or `None`
+/// if it is not such a term. Computed from the term's Lehmer code in time linear in its length,
+/// without building a table.
+///
+/// Panics if `variables > 128` or if the index does not fit in a `usize`.
+pub fn rank(variables: usize, term: &[usize]) -> Option<usize> {
+    assert!(variables <= 128, "ranking supports at most 128 variables");
+    if term.is_empty() || term.len() > variables {
+        return None;
+    }
+    let mut used = 0u128;
+    let mut index = 0usize;
+    for (position, &variable) in term.iter().enumerate() {
+        if variable >= variables || used & 1 << variable != 0 {
+            return None;
+        }
+        // the number of still unused variables smaller than this one
+        let digit = variable - (used & ((1 << variable) - 1)).count_ones() as usize;
+        index = index
+            .checked_mul(variables - position)
+            .and_then(|index| index.checked_add(digit))
+            .expect("term index overflows usize");
+        used |= 1 << variable;
+    }
+    let offset = (1..term.len())
+        .try_fold(index, |offset, len| {
+            offset.checked_add(partial_permutations(variables, len)?)
+        })
+        .expect("term index overflows usize");
+    Some(offset)
+}
+
+/// The term at `index` among the terms over `variables` variables, in `TermTable` order, or `None`
+/// if there are not that many terms. The inverse of `rank`.
+///
+/// Panics if `variables > 128`.
+pub fn unrank(variables: usize, mut index: usize) -> Option<Term> {
+    assert!(variables <= 128, "ranking supports at most 128 variables");
+    let mut len = 1;
+    loop {
+        if len > variables {
+            return None;
+        }
+        match partial_permutations(variables, len) {
+            Some(count) if index >= count => index -= count,
+            _ => break,
+        }
+        len += 1;
+    }
+    let mut used = 0u128;
+    let mut weight = partial_permutations(variables - 1, len - 1).unwrap();
+    let mut term = Vec::with_capacity(len);
+    for position in 0..len {
+        let digit = index / weight;
+        index %= weight;
+        if position + 1 < len {
+            weight /= variables - position - 1;
+        }
+        let variable = (0..variables)
+            .filter(|&variable| used & 1 << variable == 0)
+            .nth(digit)
+            .unwrap();
+        used |= 1 << variable;
+        term.push(variable);
+    }
+    Some(Term(term))
+}
+
+// the number of terms of length `len` over `variables` variables, if it fits in a `usize`
+fn partial_permutations(variables: usize, len: usize) -> Option<usize> {
+    (variables - len + 1..=variables).try_fold(1usize, usize::checked_mul)
+}
+
+// the maximal order of the table `TermTable::bounded` builds for the arguments, which all bounded
+// machines use: the single-variable terms are always kept, and there are no terms longer than
+// `variables`
+pub(crate) fn clamp_max_order(variables: usize, max_order: usize) -> usize {
+    max_order.max(1).min(variables)
+}
+
+/// The terms of machines over a given number of variables, in the order in which machines store
+/// their values: by length, and lexicographically within each length. In particular, the
+/// single-variable term `[v]` always has index `v`.
+///
+/// A bounded table only holds the terms of at most `max_order` variables. Because of the order,
+/// these are a prefix of the full table, so terms have the same index in both.
+///
+/// Besides the terms, a table holds, for each variable, the terms of two or more variables that
+/// start with it, together with their tails. These buckets are all a `flip` visits, so flipping
+/// takes time linear in the bucket of the flipped variable, a `1/variables` share of the table.
+///
+/// Tables are immutable once built. `TermTable::shared` hands out one table per variable count
+/// from a process-wide cache; tables built with `TermTable::new` are independent of that cache,
+/// which lets callers decide how long the (factorially large) tables are kept alive.
+pub struct TermTable {
+    variables: usize,
+    max_order: usize,
+    terms: Vec<Term>,
+    cascades: Vec<Cascade>,
+}
+
+// the term indices `flip` touches for one variable, besides the variable's own index
+pub(crate) struct Cascade {
+    // the indices of each longer term starting with the variable and of that term's tail
+    pub(crate) terms: Vec<(usize, usize)>,
+}
+
+impl TermTable {
+    pub fn new(variables: usize) -> Self {
+        Self::bounded(variables, variables)
+    }
+
+    /// Like `new`, but only with the terms of at most `max_order` variables. A `max_order` of
+    /// `variables` or more gives the full table, and the single-variable terms are always kept, so
+    /// a `max_order` of 0 is taken as 1. Every bounded machine, however it is built, clamps its
+    /// maximal order this way.
+    pub fn bounded(variables: usize, max_order: usize) -> Self {
+        let max_order = clamp_max_order(variables, max_order);
+        let terms = (1..=max_order)
+            .flat_map(|len| (0..variables).permutations(len))
+            .map(Term)
+            .collect_vec();
+        // one pass over the terms, so that building stays linear in the size of bounded tables
+        let mut cascades = (0..variables)
+            .map(|_| Cascade { terms: Vec::new() })
+            .collect_vec();
+        for (index, term) in terms.iter().enumerate().skip(variables) {
+            let tail = rank(variables, &term[1..]).unwrap();
+            cascades[term[0]].terms.push((index, tail));
+        }
+        Self {
+            variables,
+            max_order,
+            terms,
+            cascades,
+        }
+    }
+
+    /// Returns the process-wide table for `variables` variables, building it on first use.
+    ///
+    /// Without the `std` feature, there is no process-wide cache and every call builds a new
+    /// table; a `TermTables` can be used to share tables instead.
+    pub fn shared(variables: usize) -> Arc<Self> {
+        Self::shared_bounded(variables, variables)
+    }
+
+    /// Returns the process-wide table for `variables` variables and terms of at most `max_order`
+    /// variables, building it on first use. See `shared` for builds without `std`.
+    #[cfg(feature = "std")]
+    pub fn shared_bounded(variables: usize, max_order: usize) -> Arc<Self> {
+        let key = TermTables::key(variables, max_order);
+        let cache = SHARED.get_or_init(Default::default);
+        if let Some(table) = cache.read().unwrap().tables.get(&key) {
+            return table.clone();
+        }
+        // built without holding the lock, so other variable counts stay available meanwhile
+        let table = Arc::new(Self::bounded(key.0, key.1));
+        cache
+            .write()
+            .unwrap()
+            .tables
+            .entry(key)
+            .or_insert(table)
+            .clone()
+    }
+
+    /// Returns the process-wide table for `variables` variables and terms of at most `max_order`
+    /// variables, building it on first use. See `shared` for builds without `std`.
+    #[cfg(not(feature = "std"))]
+    pub fn shared_bounded(variables: usize, max_order: usize) -> Arc<Self> {
+        Arc::new(Self::bounded(variables, max_order))
+    }
+
+    pub fn variables(&self) -> usize {
+        self.variables
+    }
+
+    /// The number of variables of the longest terms in this table.
+    pub fn max_order(&self) -> usize {
+        self.max_order
+    }
+
+    /// Whether this table lacks some of the terms over its variables.
+    pub fn is_bounded(&self) -> bool {
+        self.max_order < self.variables
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    pub fn term(&self, index: usize) -> &Term {
+        &self.terms[index]
+    }
+
+    pub fn index(&self, term: &[usize]) -> Option<usize> {
+        if term.len() > self.max_order {
+            return None;
+        }
+        rank(self.variables, term)
+    }
+
+    /// Panics if `term` is not in this table.
+    pub(crate) fn expect_index(&self, term: &[usize]) -> usize {
+        self.index(term)
+            .unwrap_or_else(|| panic!("{term:?} is not a term of this machine"))
+    }
+
+    /// Panics if `variable` is out of range.
+    pub(crate) fn variable_index(&self, variable: usize) -> usize {
+        assert!(
+            variable < self.variables,
+            "variable {variable} is out of range for a machine with {} variables",
+            self.variables
+        );
+        variable
+    }
+
+    /// The terms of two or more variables starting with `variable`, in table order: the bucket a
+    /// flip of `variable` visits.
+    ///
+    /// Panics if `variable` is out of range.
+    pub fn leading(&self, variable: usize) -> impl Iterator<Item = &Term> {
+        self.cascades[self.variable_index(variable)]
+            .terms
+            .iter()
+            .map(|&(term, _)| &self.terms[term])
+    }
+
+    pub(crate) fn cascade(&self, variable: usize) -> &Cascade {
+        &self.cascades[variable]
+    }
+
+    /// The approximate number of bytes this table occupies, including its heap allocations.
+    pub fn size_bytes(&self) -> usize {
+        let terms = self
+            .terms
+            .iter()
+            .map(|term| size_of::<Term>() + term.0.capacity() * size_of::<usize>())
+            .sum::<usize>();
+        let cascades = self
+            .cascades
+            .iter()
+            .map(|cascade| {
+                size_of::<Cascade>() + cascade.terms.capacity() * size_of::<(usize, usize)>()
+            })
+            .sum::<usize>();
+        size_of::<Self>() + terms + cascades
+    }
+}
+
+/// A cache handing out one shared table per variable count and bound, for building machines with
+/// `GenericMachine::with_table`. The process-wide cache behind `TermTable::shared` is one of these,
+/// so this is only needed without the `std` feature, or to manage tables apart from that cache.
+#[derive(Clone, Debug, Default)]
+pub struct TermTables {
+    tables: BTreeMap<(usize, usize), Arc<TermTable>>,
+}
+
+impl TermTables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The table for `variables` variables, built on first use.
+    pub fn get(&mut self, variables: usize) -> Arc<TermTable> {
+        self.get_bounded(variables, variables)
+    }
+
+    /// The table for `variables` variables and terms of at most `max_order` variables, built on
+    /// first use.
+    pub fn get_bounded(&mut self, variables: usize, max_order: usize) -> Arc<TermTable> {
+        let key = Self::key(variables, max_order);
+        self.tables
+            .entry(key)
+            .or_insert_with(|| Arc::new(TermTable::bounded(key.0, key.1)))
+            .clone()
+    }
+
+    /// The number of cached tables.
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// The approximate number of bytes held by the cached tables, as by `TermTable::size_bytes`.
+    pub fn size_bytes(&self) -> usize {
+        self.tables.values().map(|table| table.size_bytes()).sum()
+    }
+
+    /// Drops the cached tables. Tables still used by machines stay alive until those machines are
+    /// dropped.
+    pub fn clear(&mut self) {
+        self.tables.clear();
+    }
+
+    // the variable count and bound of the table that `TermTable::bounded` builds for the arguments
+    fn key(variables: usize, max_order: usize) -> (usize, usize) {
+        (variables, clamp_max_order(variables, max_order))
+    }
+}
+
+// all tables for the same variable count and bound have the same contents
+impl PartialEq for TermTable {
+    fn eq(&self, other: &Self) -> bool {
+        (self.variables, self.max_order) == (other.variables, other.max_order)
+    }
+}
+
+impl Eq for TermTable {}
+
+impl PartialOrd for TermTable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TermTable {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.variables, self.max_order).cmp(&(other.variables, other.max_order))
+    }
+}
+
+impl Hash for TermTable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.variables, self.max_order).hash(state);
+    }
+}
+
+impl Debug for TermTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TermTable")
+            .field("variables", &self.variables)
+            .field("max_order", &self.max_order)
+            .field("len", &self.terms.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::terms::{self, rank, unrank};
+    use crate::{Machine, Term, TermTable, TermTables};
+    use itertools::Itertools;
+    use std::sync::Arc;
+
+    #[test]
+    fn order() {
+        let table = TermTable::new(3);
+        assert_eq!(table.len(), 15);
+        assert_eq!(table.term(0), &Term::new([0]));
+        assert_eq!(table.term(3), &Term::new([0, 1]));
+        assert_eq!(table.term(14), &Term::new([2, 1, 0]));
+        for (index, term) in table.terms().iter().enumerate() {
+            assert_eq!(table.index(term), Some(index));
+        }
+        assert_eq!(table.index(&[0, 0]), None);
+        for variable in 0..3 {
+            assert_eq!(table.index(&[variable]), Some(variable));
+        }
+    }
+
+    #[test]
+    fn ranking() {
+        for variables in 0..=5 {
+            let table = TermTable::new(variables);
+            for (index, term) in table.terms().iter().enumerate() {
+                assert_eq!(rank(variables, term), Some(index));
+                assert_eq!(unrank(variables, index).as_ref(), Some(term));
+            }
+            assert_eq!(unrank(variables, table.len()), None);
+        }
+        assert_eq!(rank(3, &[]), None);
+        assert_eq!(rank(3, &[1, 1]), None);
+        assert_eq!(rank(3, &[3]), None);
+        assert_eq!(rank(20, &[19]), Some(19));
+        assert_eq!(rank(20, &[0, 1]), Some(20));
+        let last = (0..20).rev().collect_vec();
+        let index = rank(20, &last).unwrap();
+        assert_eq!(unrank(20, index), Some(Term::new(last)));
+        assert_eq!(unrank(20, index + 1), None);
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let expected = Machine::all(3)
+            .into_iter()
+            .map(|mut machine| {
+                machine.flip(1);
+                machine
+            })
+            .collect_vec();
+        let table = Arc::as_ptr(&TermTable::shared(3));
+        let handles = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let machines = Machine::all(3)
+                        .into_iter()
+                        .map(|mut machine| {
+                            machine.flip(1);
+                            machine
+                        })
+                        .collect_vec();
+                    (machines, Arc::as_ptr(&TermTable::shared(3)) as usize)
+                })
+            })
+            .collect_vec();
+        for handle in handles {
+            let (machines, thread_table) = handle.join().unwrap();
+            assert_eq!(machines, expected);
+            assert_eq!(thread_table, table as usize);
+        }
+    }
+
+    #[test]
+    fn bounded() {
+        let full = TermTable::new(4);
+        let pairs = TermTable::bounded(4, 2);
+        assert_eq!(pairs.len(), 4 + 12);
+        assert_eq!(pairs.terms(), &full.terms()[..16]);
+        assert!(pairs.is_bounded() && !full.is_bounded());
+        assert_eq!(pairs.index(&[3, 2]), Some(15));
+        assert_eq!(pairs.index(&[0, 1, 2]), None);
+        assert_ne!(pairs, full);
+        assert_eq!(TermTable::bounded(4, 7), full);
+        assert!(Arc::ptr_eq(
+            &TermTable::shared_bounded(4, 4),
+            &TermTable::shared(4)
+        ));
+        // cascades only reach the stored terms
+        assert!(pairs.cascade(0).terms.iter().all(|&(term, _)| term < 16));
+        assert_eq!(pairs.cascade(0).terms.len(), 3);
+    }
+
+    #[test]
+    fn leading_buckets() {
+        let table = TermTable::new(4);
+        for variable in 0..4 {
+            assert!(
+                table
+                    .leading(variable)
+                    .all(|term| term[0] == variable && term.len() > 1)
+            );
+            assert_eq!(table.leading(variable).count(), (table.len() - 4) / 4);
+            assert!(
+                table
+                    .leading(variable)
+                    .is_sorted_by_key(|term| table.index(term))
+            );
+        }
+        assert_eq!(
+            TermTable::bounded(4, 2).leading(1).collect_vec(),
+            [&Term::new([1, 0]), &Term::new([1, 2]), &Term::new([1, 3])]
+        );
+    }
+
+    #[test]
+    fn private_table() {
+        let table = Arc::new(TermTable::new(2));
+        let mut private = Machine::with_table(table.clone(), |term| term == [0, 1]);
+        let mut shared = Machine::new(2, |term| term == [0, 1]);
+        assert!(Arc::ptr_eq(private.table(), &table));
+        assert!(!Arc::ptr_eq(private.table(), shared.table()));
+        private.flip(0);
+        shared.flip(0);
+        assert_eq!(private, shared);
+    }
+
+    #[test]
+    fn explicit_cache() {
+        let mut tables = TermTables::new();
+        let table = tables.get(3);
+        assert!(Arc::ptr_eq(&table, &tables.get_bounded(3, 5)));
+        assert!(!Arc::ptr_eq(&table, &TermTable::shared(3)));
+        assert_eq!(*table, *TermTable::shared(3));
+        assert_eq!(tables.get_bounded(3, 0).max_order(), 1);
+    }
+
+    #[test]
+    fn cache_management() {
+        let mut tables = TermTables::new();
+        assert_eq!(tables.size_bytes(), 0);
+        let small = tables.get(2);
+        let large = tables.get(4);
+        assert_eq!(tables.len(), 2);
+        assert!(large.size_bytes() > small.size_bytes());
+        assert!(small.size_bytes() >= 4 * size_of::<Term>());
+        assert_eq!(tables.size_bytes(), small.size_bytes() + large.size_bytes());
+        tables.clear();
+        assert!(tables.is_empty());
+        assert_eq!(tables.size_bytes(), 0);
+        // tables in use outlive the cache
+        assert_eq!(large.len(), 64);
+        assert!(!Arc::ptr_eq(&large, &tables.get(4)));
+
+        // `terms::clear` is not called here, as other tests rely on the process-wide cache
+        terms::precompute(5);
+        assert!(terms::cache_size_bytes() >= TermTable::shared(5).size_bytes());
+    }
+}
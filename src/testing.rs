@@ -0,0 +1,205 @@
+//! Differential testing of the machine representations against each other: random machines and
+//! flip sequences are run on every backend, and the observables after each flip must agree with
+//! those of the dense `Machine`, which serves as the reference.
+//!
+//! Backends that cannot represent a machine are skipped for it: `FixedMachine` above 4 variables
+//! and `SubsetMachine` for machines that are not symmetric.
+
+use crate::{
+    DifferentialSystem, FixedMachine, GenericMachine, Machine, Modular, PackedMachine,
+    SparseMachine, SubsetMachine, Term, Value,
+};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use itertools::Itertools;
+use rand::Rng;
+
+/// The backends compared with the dense `Machine`: `SparseMachine`, `PackedMachine`,
+/// `FixedMachine`, a bounded `Machine` with the maximal order of the machine's nonzero terms,
+/// `SubsetMachine`, and `GenericMachine<Modular<2>>`.
+pub const BACKENDS: &[&str] = &["sparse", "packed", "fixed", "bounded", "subset", "modular"];
+
+/// A machine and flip sequence on which a backend diverges from the dense `Machine`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mismatch {
+    pub backend: &'static str,
+    pub machine: Machine,
+    pub flips: Vec<usize>,
+    /// The observables of the dense machine, initially and after each flip.
+    pub expected: Vec<u64>,
+    /// The observables of the backend, initially and after each flip.
+    pub actual: Vec<u64>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the {} backend diverges on machine `{}` with flips {:?}: expected observables {:?}, \
+             found {:?}",
+            self.backend, self.machine, self.flips, self.expected, self.actual
+        )
+    }
+}
+
+/// Runs `flips` on `machine` in every backend and returns the first mismatch, if any.
+///
+/// Panics if `machine` has more than 64 variables or a flip is out of range.
+pub fn cross_check(machine: &Machine, flips: &[usize]) -> Result<(), Box<Mismatch>> {
+    let expected = trace(machine.clone(), flips);
+    for &backend in BACKENDS {
+        if let Some(actual) = backend_trace(backend, machine, flips)
+            && actual != expected
+        {
+            return Err(Box::new(Mismatch {
+                backend,
+                machine: machine.clone(),
+                flips: flips.to_vec(),
+                expected,
+                actual,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Cross-checks `runs` random machines with at most `max_variables` variables, each with a random
+/// sequence of at most `max_flips` flips. A mismatch is minimized before it is returned, by
+/// dropping flips and clearing terms for as long as the backend keeps diverging.
+pub fn fuzz(
+    rng: &mut impl Rng,
+    runs: usize,
+    max_variables: usize,
+    max_flips: usize,
+) -> Result<(), Box<Mismatch>> {
+    for _ in 0..runs {
+        let variables = rng.random_range(0..=max_variables);
+        let density = rng.random();
+        let machine = Machine::random_with_density(variables, density, rng);
+        let flips = match variables {
+            0 => Vec::new(),
+            _ => (0..rng.random_range(0..=max_flips))
+                .map(|_| rng.random_range(0..variables))
+                .collect(),
+        };
+        if let Err(mismatch) = cross_check(&machine, &flips) {
+            let backend = mismatch.backend;
+            let (machine, flips) = minimize(machine, flips, |machine, flips| {
+                backend_trace(backend, machine, flips)
+                    .is_some_and(|actual| actual != trace(machine.clone(), flips))
+            });
+            return cross_check(&machine, &flips);
+        }
+    }
+    Ok(())
+}
+
+// greedily drops flips and clears terms while `fails` keeps holding
+fn minimize(
+    mut machine: Machine,
+    mut flips: Vec<usize>,
+    fails: impl Fn(&Machine, &[usize]) -> bool,
+) -> (Machine, Vec<usize>) {
+    let mut shrunk = true;
+    while shrunk {
+        shrunk = false;
+        for index in (0..flips.len()).rev() {
+            let mut shorter = flips.clone();
+            shorter.remove(index);
+            if fails(&machine, &shorter) {
+                flips = shorter;
+                shrunk = true;
+            }
+        }
+        let true_terms = machine
+            .terms()
+            .filter(|&(_, value)| value)
+            .map(|(term, _)| term.clone())
+            .collect_vec();
+        for term in true_terms {
+            let mut cleared = machine.clone();
+            cleared.set_term(&term, false);
+            if fails(&cleared, &flips) {
+                machine = cleared;
+                shrunk = true;
+            }
+        }
+    }
+    (machine, flips)
+}
+
+// the observables of `backend` running `flips` on `machine`, or `None` if it cannot represent it
+fn backend_trace(backend: &str, machine: &Machine, flips: &[usize]) -> Option<Vec<u64>> {
+    let value = |term: &[usize]| machine.get_term(&Term::from(term));
+    Some(match backend {
+        "sparse" => trace(SparseMachine::from(machine), flips),
+        "packed" => trace(PackedMachine::from(machine), flips),
+        "fixed" => match machine.variables() {
+            0 => trace(FixedMachine::<0>::try_from(machine).ok()?, flips),
+            1 => trace(FixedMachine::<1>::try_from(machine).ok()?, flips),
+            2 => trace(FixedMachine::<2>::try_from(machine).ok()?, flips),
+            3 => trace(FixedMachine::<3>::try_from(machine).ok()?, flips),
+            4 => trace(FixedMachine::<4>::try_from(machine).ok()?, flips),
+            _ => return None,
+        },
+        "bounded" => trace(
+            Machine::new_bounded(machine.variables(), machine.max_order(), value),
+            flips,
+        ),
+        "subset" => trace(SubsetMachine::try_from(machine).ok()?, flips),
+        "modular" => trace(
+            GenericMachine::new(machine.variables(), |term| {
+                Modular::<2>::new(value(term) as u8)
+            }),
+            flips,
+        ),
+        _ => unreachable!("unknown backend {backend}"),
+    })
+}
+
+// the observables initially and after each flip, with bit `v` set if variable `v` is nonzero
+fn trace<S: DifferentialSystem>(mut system: S, flips: &[usize]) -> Vec<u64> {
+    assert!(system.variables() <= 64, "more than 64 variables");
+    let observables = |system: &S| {
+        (0..system.variables())
+            .filter(|&variable| system.get(variable) != S::Value::ZERO)
+            .fold(0, |mask, variable| mask | 1 << variable)
+    };
+    let mut observed = vec![observables(&system)];
+    for &variable in flips {
+        system.flip(variable);
+        observed.push(observables(&system));
+    }
+    observed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{cross_check, fuzz, minimize};
+    use crate::{Machine, Term};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn backends_agree() {
+        let mut rng = StdRng::seed_from_u64(566);
+        assert_eq!(fuzz(&mut rng, 200, 4, 12), Ok(()));
+        assert_eq!(fuzz(&mut rng, 5, 6, 12), Ok(()));
+        let symmetric = Machine::new(3, |term| term.len() == 2);
+        assert_eq!(cross_check(&symmetric, &[0, 1, 2, 2, 0]), Ok(()));
+    }
+
+    #[test]
+    fn minimization() {
+        let machine = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2] | [2, 0, 1]));
+        // a stand-in for a backend that mishandles the cascade of [0, 1]
+        let fails = |machine: &Machine, flips: &[usize]| {
+            machine.get_term(&Term::new([0, 1])) && flips.contains(&0)
+        };
+        let (machine, flips) = minimize(machine, vec![2, 1, 0, 0, 2], fails);
+        assert_eq!(machine, Machine::new(3, |term| term == [0, 1]));
+        assert_eq!(flips, [0]);
+    }
+}
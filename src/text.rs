@@ -0,0 +1,226 @@
+//! A compact text format for `bool` machines: a comma-separated list of `term:value` entries,
+//! where the value is `+` for true and `-` for false, e.g. `0:-, 1:-, 01:+, 10:-`.
+//!
+//! Terms are written as one digit per variable, or, for machines with more than ten variables, as
+//! variables separated by `.`, with single variables followed by a `.` (e.g. `3.11:+, 10.:-`). A
+//! text using `.` anywhere is read entirely in the second notation, so every printed machine is
+//! read back in the notation it was written in. The number of variables is one more than the
+//! largest variable mentioned, and terms without an entry are false.
+//!
+//! The format has no maximal order, so bounded machines are not supported: their text is read back
+//! as the full machine with the same true terms.
+
+use crate::collections::HashMap;
+use crate::{Machine, MachineError};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+use itertools::Itertools;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseMachineError {
+    /// An entry is not of the form `term:value`.
+    MissingSeparator(String),
+    /// A value is neither `+` nor `-`.
+    InvalidValue(String),
+    /// A term contains something other than variable numbers.
+    InvalidTerm(String),
+    DuplicateTerm(Vec<usize>),
+    Machine(MachineError),
+}
+
+impl Display for ParseMachineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseMachineError::MissingSeparator(entry) => {
+                write!(f, "entry {entry:?} is not of the form `term:value`")
+            }
+            ParseMachineError::InvalidValue(entry) => {
+                write!(f, "entry {entry:?} has a value other than `+` or `-`")
+            }
+            ParseMachineError::InvalidTerm(entry) => {
+                write!(f, "entry {entry:?} has an invalid term")
+            }
+            ParseMachineError::DuplicateTerm(term) => {
+                write!(f, "term {term:?} is given more than once")
+            }
+            ParseMachineError::Machine(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for ParseMachineError {}
+
+impl From<MachineError> for ParseMachineError {
+    fn from(error: MachineError) -> Self {
+        ParseMachineError::Machine(error)
+    }
+}
+
+impl Display for Machine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let separator = if self.variables() > 10 { "." } else { "" };
+        let entries = self.terms().map(|(term, value)| {
+            // single variables get their `.` too, so bounded tables of one order stay dotted
+            let single = if term.len() == 1 { separator } else { "" };
+            format!(
+                "{}{single}:{}",
+                term.iter().join(separator),
+                if value { '+' } else { '-' }
+            )
+        });
+        write!(f, "{}", entries.format(", "))
+    }
+}
+
+impl FromStr for Machine {
+    type Err = ParseMachineError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let dotted = text.contains('.');
+        let values = parse_entries(text, |term| {
+            if dotted {
+                let term = term.strip_suffix('.').unwrap_or(term);
+                term.split('.')
+                    .map(|variable| variable.parse().ok())
+                    .collect()
+            } else {
+                term.chars()
+                    .map(|variable| variable.to_digit(10).map(|variable| variable as usize))
+                    .collect()
+            }
+        })?;
+        let variables = values
+            .keys()
+            .flatten()
+            .max()
+            .map_or(0, |&variable| variable + 1);
+        build(variables, values)
+    }
+}
+
+// splits `text` into its entries and parses their terms with `parse_term`, which returns `None`
+// for invalid terms
+pub(crate) fn parse_entries(
+    text: &str,
+    parse_term: impl Fn(&str) -> Option<Vec<usize>>,
+) -> Result<HashMap<Vec<usize>, bool>, ParseMachineError> {
+    let mut values = HashMap::new();
+    for entry in text
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (term, value) = entry
+            .split_once(':')
+            .ok_or_else(|| ParseMachineError::MissingSeparator(entry.to_string()))?;
+        let value = match value.trim() {
+            "+" => true,
+            "-" => false,
+            _ => return Err(ParseMachineError::InvalidValue(entry.to_string())),
+        };
+        let term = parse_term(term.trim())
+            .filter(|term| !term.is_empty())
+            .ok_or_else(|| ParseMachineError::InvalidTerm(entry.to_string()))?;
+        if values.contains_key(&term) {
+            return Err(ParseMachineError::DuplicateTerm(term));
+        }
+        values.insert(term, value);
+    }
+    Ok(values)
+}
+
+pub(crate) fn build(
+    variables: usize,
+    values: HashMap<Vec<usize>, bool>,
+) -> Result<Machine, ParseMachineError> {
+    Ok(values
+        .into_iter()
+        .fold(Machine::builder(variables), |builder, (term, value)| {
+            builder.term(&term, value)
+        })
+        .build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::ParseMachineError;
+    use crate::{Machine, MachineError};
+
+    #[test]
+    fn display() {
+        let machine = Machine::new(2, |term| matches!(term, [1] | [0, 1]));
+        assert_eq!(machine.to_string(), "0:-, 1:+, 01:+, 10:-");
+        assert_eq!(Machine::new(0, |_| false).to_string(), "");
+    }
+
+    #[test]
+    fn round_trip() {
+        for machine in Machine::all(3) {
+            assert_eq!(machine.to_string().parse::<Machine>(), Ok(machine));
+        }
+    }
+
+    #[test]
+    fn dotted() {
+        let wide = Machine::new_bounded(11, 1, |term| term == [10]);
+        let text = wide.to_string();
+        assert!(text.starts_with("0.:-, 1.:-, "));
+        assert!(text.ends_with(", 10.:+"));
+        assert_eq!(
+            "0.:-, 1.:+, 0.2:+".parse::<Machine>(),
+            Ok(Machine::new(3, |term| matches!(term, [1] | [0, 2])))
+        );
+    }
+
+    #[test]
+    fn bounded_unsupported() {
+        let producer = |term: &[usize]| matches!(term, [2] | [0, 1]);
+        let bounded = Machine::new_bounded(3, 2, producer);
+        assert_eq!(
+            bounded.to_string().parse::<Machine>(),
+            Ok(Machine::new(3, producer))
+        );
+    }
+
+    #[test]
+    fn sparse_text() {
+        assert_eq!(
+            "01:+, 10:+".parse::<Machine>(),
+            Ok(Machine::new(2, |term| term.len() == 2))
+        );
+        assert_eq!(
+            "0:-,1:+ , 0.2 :+".parse::<Machine>(),
+            Ok(Machine::new(3, |term| matches!(term, [1] | [0, 2])))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            "01+".parse::<Machine>(),
+            Err(ParseMachineError::MissingSeparator("01+".to_string()))
+        );
+        assert_eq!(
+            "01:x".parse::<Machine>(),
+            Err(ParseMachineError::InvalidValue("01:x".to_string()))
+        );
+        assert_eq!(
+            "0a:+".parse::<Machine>(),
+            Err(ParseMachineError::InvalidTerm("0a:+".to_string()))
+        );
+        assert_eq!(
+            "1:+, 1:-".parse::<Machine>(),
+            Err(ParseMachineError::DuplicateTerm(vec![1]))
+        );
+        assert_eq!(
+            "00:+".parse::<Machine>(),
+            Err(ParseMachineError::Machine(MachineError::MalformedTerm(
+                vec![0, 0]
+            )))
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Recording of flips and sets as a `Trace` of events, which can be written as CSV or, with the
+//! `serde` feature, serialized for analysis in external tools.
+
+use crate::{Machine, Term};
+use itertools::Itertools;
+use std::io::{self, Write};
+use std::ops::Deref;
+
+/// A machine that appends an event to its trace for every `flip` and `set`, created by
+/// `Machine::into_traced`.
+///
+/// Only shared access to the machine is given out, so the trace cannot be bypassed.
+#[derive(Clone, Debug)]
+pub struct TracedMachine {
+    pub(crate) machine: Machine,
+    pub(crate) trace: Trace,
+}
+
+impl TracedMachine {
+    pub fn flip(&mut self, variable: usize) {
+        self.record(Operation::Flip, variable, |machine| {
+            machine.flip_traced(variable)
+        });
+    }
+
+    /// Sets `variable` as by `Machine::set`. Setting a variable to its current value is recorded
+    /// as an event without changes.
+    pub fn set(&mut self, variable: usize, value: bool) {
+        self.record(Operation::Set(value), variable, |machine| {
+            if machine.get(variable) == value {
+                Vec::new()
+            } else {
+                machine.flip_traced(variable)
+            }
+        });
+    }
+
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    pub fn into_parts(self) -> (Machine, Trace) {
+        (self.machine, self.trace)
+    }
+
+    fn record(
+        &mut self,
+        operation: Operation,
+        variable: usize,
+        apply: impl FnOnce(&mut Machine) -> Vec<Vec<usize>>,
+    ) {
+        let before = self.machine.observables();
+        let changed = apply(&mut self.machine)
+            .into_iter()
+            .map(Term::from)
+            .collect();
+        self.trace.events.push(TraceEvent {
+            operation,
+            variable,
+            before,
+            after: self.machine.observables(),
+            changed,
+        });
+    }
+}
+
+impl Deref for TracedMachine {
+    type Target = Machine;
+
+    fn deref(&self) -> &Self::Target {
+        &self.machine
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Operation {
+    Flip,
+    Set(bool),
+}
+
+/// One recorded operation. The observables are bit masks as returned by `Machine::observables`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct TraceEvent {
+    pub operation: Operation,
+    pub variable: usize,
+    pub before: u64,
+    pub after: u64,
+    /// The terms whose value changed, in the order in which they were changed.
+    pub changed: Vec<Term>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Trace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    /// Writes one line per event after a header, with the columns `step`, `operation` (`flip` or
+    /// `set`), `variable`, `value` (empty for flips), `before`, `after` and `changed`. The changed
+    /// terms are separated by spaces, with the variables of each term separated by `.`.
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "step,operation,variable,value,before,after,changed")?;
+        for (step, event) in self.events.iter().enumerate() {
+            let (operation, value) = match event.operation {
+                Operation::Flip => ("flip", String::new()),
+                Operation::Set(value) => ("set", value.to_string()),
+            };
+            let changed = event
+                .changed
+                .iter()
+                .map(|term| term.iter().join("."))
+                .join(" ");
+            writeln!(
+                out,
+                "{step},{operation},{},{value},{},{},{changed}",
+                event.variable, event.before, event.after
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = Vec::new();
+        self.write_csv(&mut csv).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::{Operation, TraceEvent};
+    use crate::{Machine, Term};
+
+    #[test]
+    fn records_events() {
+        let mut system = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2])).into_traced();
+        system.flip(0);
+        system.set(1, true);
+        system.set(1, false);
+        assert_eq!(system.observables(), 0b101);
+        let (machine, trace) = system.into_parts();
+        assert_eq!(trace.events.len(), 3);
+        assert_eq!(
+            trace.events[0],
+            TraceEvent {
+                operation: Operation::Flip,
+                variable: 0,
+                before: 0b000,
+                after: 0b011,
+                changed: vec![Term::new([0]), Term::new([1])],
+            }
+        );
+        assert!(trace.events[1].changed.is_empty());
+        assert_eq!(trace.events[2].after, machine.observables());
+        assert_eq!(
+            trace.to_csv(),
+            "step,operation,variable,value,before,after,changed\n\
+             0,flip,0,,0,3,0 1\n\
+             1,set,1,true,3,3,\n\
+             2,set,1,false,3,5,1 2\n"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::system::DifferentialSystem;
+use crate::{Machine, MachineError};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A sequence of flips, taken as the transformation it applies to any machine with enough
+/// variables. Transformations compose with `then` and `pow`, and are compared by their flips, not
+/// by their effect: `[0, 0]` and the identity differ, although both leave every `Machine`
+/// unchanged.
+///
+/// As flipping a variable is a bijection of the machines, applying a transformation often enough
+/// always returns a machine to itself; `period` counts the applications for one machine, and
+/// `order` for all machines over some number of variables at once.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Transformation {
+    flips: Vec<usize>,
+}
+
+impl Transformation {
+    /// The transformation that flips nothing.
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// The transformation that flips the given variables one after the other.
+    pub fn from_flips(flips: &[usize]) -> Self {
+        Self {
+            flips: flips.to_vec(),
+        }
+    }
+
+    pub fn flips(&self) -> &[usize] {
+        &self.flips
+    }
+
+    /// The number of variables a machine needs for the transformation to apply to it, one more
+    /// than the greatest flipped variable.
+    pub fn variables(&self) -> usize {
+        self.flips.iter().max().map_or(0, |&variable| variable + 1)
+    }
+
+    /// Flips the variables of `system` in order, or fails without flipping any if one of them is
+    /// out of range.
+    pub fn apply<S: DifferentialSystem>(&self, system: &mut S) -> Result<(), MachineError> {
+        if self.variables() > system.variables() {
+            return Err(MachineError::VariableOutOfRange {
+                variable: self.variables() - 1,
+                variables: system.variables(),
+            });
+        }
+        for &variable in &self.flips {
+            system.flip(variable);
+        }
+        Ok(())
+    }
+
+    /// The transformation that applies `self` and then `other`.
+    pub fn then(&self, other: &Self) -> Self {
+        Self {
+            flips: [&self.flips[..], &other.flips].concat(),
+        }
+    }
+
+    /// The transformation that applies `self` `exponent` times.
+    pub fn pow(&self, exponent: usize) -> Self {
+        Self {
+            flips: self.flips.repeat(exponent),
+        }
+    }
+
+    /// The transformation that undoes `self` on machines over `bool`, where every flip is its own
+    /// inverse: the same flips in reverse order. For other values, such as `Modular`, the inverse
+    /// on a given machine is `self.pow(self.period(machine) - 1)`.
+    pub fn inverse(&self) -> Self {
+        Self {
+            flips: self.flips.iter().rev().copied().collect(),
+        }
+    }
+
+    /// The number of applications of `self` after which `system` first returns to itself.
+    ///
+    /// Panics if a flip is out of range.
+    pub fn period<S: DifferentialSystem>(&self, system: &S) -> usize {
+        let mut current = system.clone();
+        let mut period = 0;
+        loop {
+            self.apply(&mut current).unwrap();
+            period += 1;
+            if current == *system {
+                return period;
+            }
+        }
+    }
+
+    /// The least number of applications of `self` after which every machine over `variables`
+    /// variables returns to itself, the least common multiple of their periods.
+    ///
+    /// Panics if a flip is out of range or `variables > 3`, as there are 2^64 machines over 4
+    /// variables.
+    pub fn order(&self, variables: usize) -> usize {
+        assert!(
+            variables <= 3,
+            "too many machines over {variables} variables"
+        );
+        assert!(
+            self.variables() <= variables,
+            "variable {} is out of range for machines with {variables} variables",
+            self.variables() - 1
+        );
+        let machines = Machine::iter_all(variables);
+        let mut seen = vec![false; machines.size_hint().0];
+        let mut order = 1;
+        for mut machine in machines {
+            // walks the cycle of `machine`, all of whose members have the same period, unless the
+            // walk from an earlier member did
+            let mut period = 0;
+            loop {
+                let signature = machine.signature().unwrap() as usize;
+                if seen[signature] {
+                    break;
+                }
+                seen[signature] = true;
+                self.apply(&mut machine).unwrap();
+                period += 1;
+            }
+            if period > 0 {
+                order = lcm(order, period);
+            }
+        }
+        order
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenericMachine, Machine, MachineError, Modular, Transformation, Value};
+
+    #[test]
+    fn apply() {
+        let transformation = Transformation::from_flips(&[0, 2, 1]);
+        assert_eq!(transformation.variables(), 3);
+        let start = Machine::new(3, |term| matches!(term, [0, 1] | [1, 2]));
+        let mut machine = start.clone();
+        transformation.apply(&mut machine).unwrap();
+        let mut expected = start.clone();
+        for variable in [0, 2, 1] {
+            expected.flip(variable);
+        }
+        assert_eq!(machine, expected);
+        assert_eq!(
+            transformation.apply(&mut Machine::new(2, |_| false)),
+            Err(MachineError::VariableOutOfRange {
+                variable: 2,
+                variables: 2
+            })
+        );
+    }
+
+    #[test]
+    fn algebra() {
+        let a = Transformation::from_flips(&[0, 1]);
+        let b = Transformation::from_flips(&[1, 1, 0]);
+        assert_eq!(a.then(&b).flips(), [0, 1, 1, 1, 0]);
+        assert_eq!(a.pow(2), a.then(&a));
+        assert_eq!(a.pow(0), Transformation::identity());
+        for start in Machine::all(2) {
+            let mut machine = start.clone();
+            a.then(&b)
+                .then(&b.inverse())
+                .then(&a.inverse())
+                .apply(&mut machine)
+                .unwrap();
+            assert_eq!(machine, start);
+            let mut machine = start.clone();
+            a.pow(a.period(&start)).apply(&mut machine).unwrap();
+            assert_eq!(machine, start);
+        }
+    }
+
+    #[test]
+    fn order() {
+        assert_eq!(Transformation::identity().order(2), 1);
+        assert_eq!(Transformation::from_flips(&[1]).order(2), 2);
+        for flips in [&[0, 1][..], &[0, 1, 2], &[2, 0, 0, 1]] {
+            let transformation = Transformation::from_flips(flips);
+            let order = transformation.order(3);
+            let periods = Machine::all(3)
+                .iter()
+                .map(|machine| transformation.period(machine))
+                .collect::<Vec<_>>();
+            assert!(periods.iter().all(|period| order.is_multiple_of(*period)));
+            assert!((1..order).all(|smaller| {
+                periods
+                    .iter()
+                    .any(|period| !smaller.is_multiple_of(*period))
+            }));
+        }
+    }
+
+    #[test]
+    fn modular_inverse() {
+        let start = GenericMachine::new(2, |term| match term {
+            [0, 1] => Modular::<3>::ONE,
+            _ => Modular::ZERO,
+        });
+        let flip = Transformation::from_flips(&[0]);
+        assert_eq!(flip.period(&start), 3);
+        let mut machine = start.clone();
+        flip.then(&flip.pow(flip.period(&start) - 1))
+            .apply(&mut machine)
+            .unwrap();
+        assert_eq!(machine, start);
+    }
+}
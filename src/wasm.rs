@@ -0,0 +1,171 @@
+//! JavaScript bindings through `wasm-bindgen`, built with the `wasm` feature, e.g. by `cargo rustc
+//! --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib` followed by
+//! the `wasm-bindgen` CLI.
+//!
+//! `WasmMachine` is exported to JavaScript as `Machine`. Terms are passed as arrays of variable
+//! indices and identified by their index in term order, as in `TermTable`; machines are exported
+//! and imported in the text format of `text` and the JSON format of the `serde` support.
+
+use crate::{Machine, Term};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(js_name = Machine)]
+#[derive(Clone, Debug)]
+pub struct WasmMachine {
+    machine: Machine,
+}
+
+#[wasm_bindgen(js_class = Machine)]
+impl WasmMachine {
+    /// A machine over `variables` variables whose terms are all false.
+    #[wasm_bindgen(constructor)]
+    pub fn new(variables: usize) -> Self {
+        Self {
+            machine: Machine::new(variables, |_| false),
+        }
+    }
+
+    #[wasm_bindgen(js_name = fromText)]
+    pub fn from_text(text: &str) -> Result<Self, JsError> {
+        Ok(Self {
+            machine: text.parse()?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Self, JsError> {
+        Ok(Self {
+            machine: serde_json::from_str(json)?,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn variables(&self) -> usize {
+        self.machine.variables()
+    }
+
+    pub fn get(&self, variable: usize) -> Result<bool, JsError> {
+        Ok(self.machine.try_get(variable)?)
+    }
+
+    pub fn flip(&mut self, variable: usize) -> Result<(), JsError> {
+        Ok(self.machine.try_flip(variable)?)
+    }
+
+    pub fn set(&mut self, variable: usize, value: bool) -> Result<(), JsError> {
+        Ok(self.machine.try_set(variable, value)?)
+    }
+
+    /// Flips `variable` and returns the indices of the terms whose value changed, in the order in
+    /// which they were changed, for animating a flip's propagation.
+    #[wasm_bindgen(js_name = flipTraced)]
+    pub fn flip_traced(&mut self, variable: usize) -> Result<Vec<usize>, JsError> {
+        self.machine.try_get(variable)?;
+        let table = self.machine.table().clone();
+        Ok(self
+            .machine
+            .flip_traced(variable)
+            .iter()
+            .map(|term| table.index(term).unwrap())
+            .collect())
+    }
+
+    #[wasm_bindgen(js_name = termCount, getter)]
+    pub fn term_count(&self) -> usize {
+        self.machine.table().len()
+    }
+
+    /// The variables of the term with the given index.
+    pub fn term(&self, index: usize) -> Result<Vec<usize>, JsError> {
+        Ok(self.term_at(index)?.variables().to_vec())
+    }
+
+    #[wasm_bindgen(js_name = termValue)]
+    pub fn term_value(&self, index: usize) -> Result<bool, JsError> {
+        let term = self.term_at(index)?.clone();
+        Ok(self.machine.get_term(&term))
+    }
+
+    #[wasm_bindgen(js_name = setTerm)]
+    pub fn set_term(&mut self, index: usize, value: bool) -> Result<(), JsError> {
+        let term = self.term_at(index)?.clone();
+        self.machine.set_term(&term, value);
+        Ok(())
+    }
+
+    /// The values of all variables, as a bit mask with variable `i` at bit `i`.
+    pub fn observables(&self) -> u64 {
+        self.machine.observables()
+    }
+
+    #[wasm_bindgen(js_name = toText)]
+    pub fn to_text(&self) -> String {
+        self.machine.to_string()
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.machine).unwrap()
+    }
+
+    /// The terms grouped by order, as by `Machine::pretty`.
+    pub fn pretty(&self) -> String {
+        self.machine.pretty().to_string()
+    }
+
+    /// The graph of machines reachable by flips, in DOT format.
+    #[wasm_bindgen(js_name = toDot)]
+    pub fn to_dot(&self) -> String {
+        self.machine.to_fsm().to_dot()
+    }
+
+    fn term_at(&self, index: usize) -> Result<&Term, JsError> {
+        self.machine
+            .table()
+            .terms()
+            .get(index)
+            .ok_or_else(|| JsError::new(&format!("there is no term with index {index}")))
+    }
+}
+
+impl From<Machine> for WasmMachine {
+    fn from(machine: Machine) -> Self {
+        Self { machine }
+    }
+}
+
+impl From<WasmMachine> for Machine {
+    fn from(machine: WasmMachine) -> Self {
+        machine.machine
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Machine;
+    use crate::wasm::WasmMachine;
+
+    // only paths that succeed are tested, as creating a `JsError` requires a JavaScript host
+    #[test]
+    fn bindings() {
+        let mut machine = WasmMachine::from_text("01:+").unwrap();
+        assert_eq!(machine.variables(), 2);
+        assert_eq!(machine.term_count(), 4);
+        assert_eq!(machine.term(2).unwrap(), [0, 1]);
+        assert!(machine.term_value(2).unwrap());
+        assert_eq!(machine.flip_traced(0).unwrap(), [0, 1]);
+        assert!(machine.get(1).unwrap());
+        machine.set(1, false).unwrap();
+        machine.flip(0).unwrap();
+        assert_eq!(machine.observables(), 0b10);
+        machine.set_term(3, true).unwrap();
+        let copy = WasmMachine::from_json(&machine.to_json()).unwrap();
+        assert_eq!(Machine::from(copy), Machine::from(machine.clone()));
+        assert_eq!(machine.to_text(), "0:-, 1:+, 01:+, 10:+");
+        assert!(machine.to_dot().starts_with("digraph"));
+        assert_eq!(
+            WasmMachine::new(3).pretty(),
+            Machine::new(3, |_| false).pretty().to_string()
+        );
+    }
+}